@@ -0,0 +1,125 @@
+/**
+ * Copyright (c) 2019, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+
+// Draw a large map with a widget per square, as the game screen used to, and
+// with the MapWidget writing directly in the buffer.
+// Run with `cargo bench --bench render`
+
+use bomberust_client::fixtures::open_map;
+use bomberust_client::gen::item::{ BombItem, Bonus, DestructibleBox, Malus };
+use bomberust_client::gen::map::Map;
+use bomberust_client::gen::utils::{ Direction, SquareType };
+use bomberust_client::{ Glyphs, MapWidget };
+
+use criterion::{ criterion_group, criterion_main, Criterion };
+use tui::buffer::Buffer;
+use tui::layout::Rect;
+use tui::style::{ Color, Style };
+use tui::widgets::canvas::Canvas;
+use tui::widgets::{ Block, Borders, Paragraph, Text, Widget };
+
+const MAP_SIZE: usize = 80;
+const CELL_SIZE: u16 = 3;
+
+/**
+ * @return  a map with every kind of square and item
+ */
+fn large_map() -> Map {
+    let mut map = open_map(MAP_SIZE, MAP_SIZE);
+    for idx in 0..MAP_SIZE * MAP_SIZE {
+        match idx % 11 {
+            0 => map.squares[idx].sq_type = SquareType::Water,
+            1 => map.squares[idx].sq_type = SquareType::Block,
+            2 => map.squares[idx].sq_type = SquareType::Wall(Direction::West),
+            3 => map.items[idx] = Some(Box::new(DestructibleBox {})),
+            4 => map.items[idx] = Some(Box::new(BombItem {})),
+            5 => map.items[idx] = Some(Box::new(Bonus::MoreBombs)),
+            6 => map.items[idx] = Some(Box::new(Malus::Slow)),
+            _ => {},
+        }
+    }
+    map
+}
+
+/**
+ * A Paragraph or a Canvas per square, items found by name
+ */
+fn draw_with_widgets(map: &Map, buf: &mut Buffer) {
+    let size = CELL_SIZE as usize;
+    for x in 0..map.w {
+        for y in 0..map.h {
+            let pos = x + map.w * y;
+            let rect = Rect::new((x * size) as u16, (y * size) as u16, CELL_SIZE, CELL_SIZE);
+            let bg = match map.squares[pos].sq_type {
+                SquareType::Water => {
+                    let water = [Text::raw("~.~"), Text::raw(".~."), Text::raw("~.~")];
+                    Paragraph::new(water.iter()).wrap(true).block(Block::default().borders(Borders::NONE)).draw(rect, buf);
+                    Color::Blue
+                },
+                SquareType::Block => Color::Black,
+                SquareType::Wall(_) => {
+                    let wall = [Text::raw("╔\n║\n╚")];
+                    Paragraph::new(wall.iter()).wrap(true).block(Block::default().borders(Borders::NONE)).draw(rect, buf);
+                    Color::Yellow
+                },
+                SquareType::Empty => match &map.items[pos] {
+                    Some(i) if i.name() == "DestructibleBox" => Color::Rgb(55, 27, 0),
+                    Some(i) => {
+                        let symbol = match &*i.name() {
+                            "Bomb" => "💣",
+                            "Bonus" => "🌟",
+                            _ => "💀",
+                        };
+                        let item = [Text::raw(symbol)];
+                        Paragraph::new(item.iter()).wrap(true).block(Block::default().borders(Borders::NONE)).draw(rect, buf);
+                        Color::Yellow
+                    },
+                    None => Color::Yellow,
+                },
+            };
+            Canvas::default()
+                .block(Block::default().borders(Borders::NONE).style(Style::default().bg(bg)))
+                .paint(|_| {})
+                .draw(rect, buf);
+        }
+    }
+}
+
+fn render(c: &mut Criterion) {
+    let map = large_map();
+    let glyphs = Glyphs::default();
+    let area = Rect::new(0, 0, MAP_SIZE as u16 * CELL_SIZE, MAP_SIZE as u16 * CELL_SIZE);
+    let mut buf = Buffer::empty(area);
+
+    c.bench_function("widget per square", |b| b.iter(|| draw_with_widgets(&map, &mut buf)));
+    c.bench_function("MapWidget", |b| b.iter(|| {
+        MapWidget::new(&map, &glyphs).cell_size(CELL_SIZE, CELL_SIZE).draw(area, &mut buf)
+    }));
+}
+
+criterion_group!(benches, render);
+criterion_main!(benches);
@@ -0,0 +1,24 @@
+#![no_main]
+// Feed arbitrary bytes from the server to the client: it must never panic.
+// Run with: cargo +nightly fuzz run process_rx
+
+use bomberust_client::Client;
+use bomberust_client::fixtures::open_map;
+use bomberust_client::net::diff_msg::SerializedEvent;
+use bomberust_client::net::msg::{ MapMsg, ServerMsg, WelcomeMsg, CAPABILITIES, PROTOCOL_VERSION };
+use futures::sync::mpsc;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let (tx, _rx) = mpsc::channel::<Vec<u8>>(16);
+    let mut client = Client::new(tx);
+    // Start in a game with every optional message enabled, so the diffs are applied
+    let capabilities = CAPABILITIES.iter().map(|c| c.to_string()).collect();
+    let _ = client.parse_rtp(ServerMsg::Welcome(WelcomeMsg { version: PROTOCOL_VERSION, capabilities }).to_vec());
+    // Map::new is random and slow: a crash must reproduce from its input alone
+    let _ = client.parse_rtp(ServerMsg::Map(MapMsg { map: open_map(11, 11), names: Vec::new() }).to_vec());
+    // Split the input to exercise packets cut between reads
+    let (first, second) = data.split_at(data.len() / 2);
+    client.process_rx(first);
+    client.process_rx(second);
+});
@@ -0,0 +1,223 @@
+/**
+ * Copyright (c) 2019, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+
+use crate::gen::item::Walkable;
+use crate::gen::map::Map;
+
+use std::time::{ Duration, Instant };
+
+/// Time between a bomb being put and its explosion, as configured on the server
+pub const FUSE_TIME: Duration = Duration::from_secs(3);
+/// How long the flames of an explosion are shown
+pub const FLAME_TIME: Duration = Duration::from_millis(500);
+
+/**
+ * A bomb waiting to explode
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LiveBomb {
+    /// Column of the bomb
+    pub x: usize,
+    /// Row of the bomb
+    pub y: usize,
+    /// Id of the player who put it
+    pub owner: i32,
+    /// Radius of the owner when the bomb was put
+    pub radius: u32,
+    /// When the bomb appeared on the map
+    pub placed: Instant,
+}
+
+impl LiveBomb {
+    /**
+     * @param now   current time
+     * @return      the estimated time before the explosion
+     */
+    pub fn remaining(&self, now: Instant) -> Duration {
+        (self.placed + FUSE_TIME).saturating_duration_since(now)
+    }
+}
+
+/**
+ * A square burning after an explosion
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Flame {
+    /// Column of the square
+    pub x: usize,
+    /// Row of the square
+    pub y: usize,
+    /// When the flame goes out
+    pub until: Instant,
+}
+
+/**
+ * Squares hit by the explosion of a bomb. The blast goes in the 4 directions and
+ * follows the explode_event of the squares and items: a square is hit if the
+ * event destroys its content, and the blast stops after a blocking square or item.
+ * @param map       the game
+ * @param bomb      position of the bomb
+ * @param radius    how many squares the blast goes in each direction
+ * @return          the squares hit, starting by the bomb
+ */
+pub fn blast(map: &Map, bomb: (usize, usize), radius: u32) -> Vec<(usize, usize)> {
+    let mut hit = vec![bomb];
+    for (dx, dy) in &[(0i64, -1i64), (0, 1), (-1, 0), (1, 0)] {
+        for dist in 1..=radius as i64 {
+            let x = bomb.0 as i64 + dx * dist;
+            let y = bomb.1 as i64 + dy * dist;
+            if x < 0 || y < 0 {
+                break;
+            }
+            let pos = (x as usize, y as usize);
+            let idx = match map.index(pos.0, pos.1) {
+                Some(idx) => idx,
+                None => break,
+            };
+            let (mut block, destroy) = map.squares[idx].sq_type.explode_event(&pos, &bomb);
+            if destroy {
+                hit.push(pos);
+                if let Some(item) = &map.items[idx] {
+                    block |= item.explode_event(&pos, &bomb).0;
+                }
+            }
+            if block {
+                break;
+            }
+        }
+    }
+    hit
+}
+
+/**
+ * When each square will burn if nothing changes. A bomb hit by another blast
+ * explodes at the same time, so the chain reactions are followed.
+ * @param map       the game
+ * @param bombs     the bombs waiting to explode
+ * @return          for each square of the map, when it will burn. None if safe
+ */
+pub fn danger_zone(map: &Map, bombs: &[LiveBomb]) -> Vec<Option<Instant>> {
+    let mut zone = vec![None; map.squares.len()];
+    let mut explosions: Vec<Instant> = bombs.iter().map(|b| b.placed + FUSE_TIME).collect();
+    let mut exploded = vec![false; bombs.len()];
+    // The next bomb to explode can only make the others explode earlier
+    while let Some(next) = (0..bombs.len()).filter(|i| !exploded[*i]).min_by_key(|i| explosions[*i]) {
+        exploded[next] = true;
+        let bomb = &bombs[next];
+        let time = explosions[next];
+        // Same radius as the flames drawn by the client when the bomb explodes
+        for (x, y) in blast(map, (bomb.x, bomb.y), bomb.radius) {
+            let idx = match map.index(x, y) {
+                Some(idx) => idx,
+                None => continue,
+            };
+            if zone[idx].is_none_or(|burn| time < burn) {
+                zone[idx] = Some(time);
+            }
+            for (other, _) in bombs.iter().enumerate().filter(|(_, b)| b.x == x && b.y == y) {
+                if !exploded[other] && time < explosions[other] {
+                    explosions[other] = time;
+                }
+            }
+        }
+    }
+    zone
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::open_map;
+    use crate::gen::item::{ BombItem, DestructibleBox };
+    use crate::gen::utils::{ Direction, SquareType };
+
+    #[test]
+    fn blast_in_open_space() {
+        let map = open_map(11, 11);
+        let mut hit = blast(&map, (1, 1), 2);
+        hit.sort();
+        assert_eq!(hit, vec![(0, 1), (1, 0), (1, 1), (1, 2), (1, 3), (2, 1), (3, 1)]);
+    }
+
+    #[test]
+    fn blast_stops_on_blocks_and_items() {
+        let mut map = open_map(11, 11);
+        map.squares[5 + 4 * 11].sq_type = SquareType::Block; // North
+        map.items[5 + 6 * 11] = Some(Box::new(DestructibleBox {})); // South
+        map.items[7 + 5 * 11] = Some(Box::new(BombItem {})); // East
+        map.squares[4 + 5 * 11].sq_type = SquareType::Water; // West, not burning
+        let mut hit = blast(&map, (5, 5), 3);
+        hit.sort();
+        assert_eq!(hit, vec![(2, 5), (3, 5), (5, 5), (5, 6), (6, 5), (7, 5)]);
+    }
+
+    #[test]
+    fn danger_zone_follows_chain_reactions() {
+        let mut map = open_map(11, 11);
+        let now = Instant::now();
+        let bomb = |x: usize, y: usize, placed: Instant| LiveBomb { x, y, owner: -1, radius: 2, placed };
+        // The last bomb is hit by the first one, then hits the second one
+        let bombs = vec![bomb(1, 1, now), bomb(5, 1, now + Duration::from_secs(2)), bomb(3, 1, now + Duration::from_secs(1))];
+        for b in &bombs {
+            map.items[b.x + b.y * map.w] = Some(Box::new(BombItem {}));
+        }
+        let zone = danger_zone(&map, &bombs);
+        let at = |x: usize, y: usize| zone[x + y * 11];
+        let first = Some(now + FUSE_TIME);
+        assert_eq!(at(1, 1), first);
+        assert_eq!(at(3, 1), first);
+        // Hit by the second bomb, exploding with the chain
+        assert_eq!(at(5, 3), first);
+        assert_eq!(at(5, 4), None);
+        assert_eq!(at(8, 8), None);
+    }
+
+    #[test]
+    fn danger_zone_keeps_the_radius_of_the_bomb() {
+        let mut map = open_map(11, 11);
+        let bombs = [LiveBomb { x: 5, y: 5, owner: 0, radius: 1, placed: Instant::now() }];
+        // The owner found a bonus after putting the bomb
+        map.players[0].radius = 4;
+        let zone = danger_zone(&map, &bombs);
+        assert!(zone[6 + 5 * 11].is_some());
+        assert_eq!(zone[7 + 5 * 11], None);
+    }
+
+    #[test]
+    fn walls_follow_their_explode_event() {
+        let mut map = open_map(11, 11);
+        // Burns, and stops the blast going south
+        map.squares[5 + 6 * 11].sq_type = SquareType::Wall(Direction::West);
+        // Seen from the side: doesn't burn, the blast goes through
+        map.squares[6 + 5 * 11].sq_type = SquareType::Wall(Direction::West);
+        let hit = blast(&map, (5, 5), 3);
+        assert!(hit.contains(&(5, 6)));
+        assert!(!hit.contains(&(5, 7)));
+        assert!(!hit.contains(&(6, 5)));
+        assert!(hit.contains(&(7, 5)));
+    }
+}
@@ -0,0 +1,585 @@
+/**
+ * Copyright (c) 2019, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+
+use crate::net::msg::*;
+
+use bytes::BytesMut;
+use futures::sync::mpsc;
+use rmps::Serializer;
+use serde::Serialize;
+use std::convert::TryFrom;
+use std::fmt;
+use std::time::{ Duration, Instant };
+use tokio::codec::Decoder;
+use crate::gen::utils::{ Direction, PlayerEffect };
+use crate::bombs::{ blast, Flame, LiveBomb, FLAME_TIME };
+use crate::interpolation::{ Interpolation, Sample };
+use crate::prediction::Prediction;
+use crate::round::Round;
+use crate::net::{ Heartbeat, RtpCodec };
+use crate::net::diff_msg::*;
+use crate::gen::map::Map;
+use crate::gen::item::*;
+
+/// How many packets can wait to be written before `Client::send` fails
+pub const OUTBOUND_QUEUE_SIZE: usize = 64;
+
+/**
+ * Why a command can't be queued
+ */
+#[derive(Debug, PartialEq)]
+pub enum SendError {
+    /// The packet doesn't fit in the length prefix
+    TooLong,
+    /// OUTBOUND_QUEUE_SIZE packets are already waiting
+    QueueFull,
+    /// The connection is closed
+    Disconnected,
+}
+
+impl fmt::Display for SendError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SendError::TooLong => write!(f, "Packet too long"),
+            SendError::QueueFull => write!(f, "Outbound queue full"),
+            SendError::Disconnected => write!(f, "Disconnected"),
+        }
+    }
+}
+
+impl std::error::Error for SendError {}
+
+/**
+ * Where the version negotiation with the server is
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum Handshake {
+    /// Hello sent, waiting for the welcome
+    Pending,
+    /// The server answered
+    Done {
+        /// Protocol version used for this connection
+        version: u32,
+        /// Optional messages announced by the server
+        capabilities: Vec<String>,
+    },
+    /// Why the server can't be used
+    Incompatible(String),
+}
+
+/**
+ * State of a session with the server, updated by the received messages
+ */
+pub struct Client
+{
+    /// Packets for the server, see TlsClient::start
+    pub tx: mpsc::Sender<Vec<u8>>,
+    /// Map of the current game
+    pub map: Option<Map>,
+    /// Id of our player in the game
+    pub linked_id: Option<u64>,
+    /// 0 is the lobby
+    pub current_room_id: Option<u64>,
+    /// Sent again on reconnection
+    pub player_name: Option<String>,
+    /// Pings waiting for their pong
+    pub heartbeat: Heartbeat,
+    /// Version negotiation with the server
+    pub handshake: Handshake,
+    /// Packets ignored because they are invalid
+    pub protocol_errors: u64,
+    /// Received positions of the players
+    pub interpolation: Interpolation,
+    /// Moves of our player not acknowledged yet
+    pub prediction: Prediction,
+    /// Names of the players of the game, by id. Empty if unknown
+    pub player_names: Vec<String>,
+    /// Active effects of the players of the game, by id
+    pub effects: Vec<Vec<PlayerEffect>>,
+    /// Deaths and result of the current game
+    pub round: Option<Round>,
+    /// Bombs of the current game, in the order they were put
+    pub bombs: Vec<LiveBomb>,
+    /// Squares burning after the last explosions
+    pub flames: Vec<Flame>,
+    rx_buffer: BytesMut, // Incomplete packet for process_rx
+}
+
+impl Client {
+    /**
+     * @param tx    where to queue the packets for the server
+     */
+    pub fn new(tx: mpsc::Sender<Vec<u8>>) -> Client {
+        Client {
+            tx,
+            map: None,
+            linked_id: None,
+            current_room_id: None,
+            player_name: None,
+            heartbeat: Heartbeat::new(),
+            handshake: Handshake::Pending,
+            protocol_errors: 0,
+            interpolation: Interpolation::default(),
+            prediction: Prediction::default(),
+            player_names: Vec::new(),
+            effects: Vec::new(),
+            round: None,
+            bombs: Vec::new(),
+            flames: Vec::new(),
+            rx_buffer: BytesMut::new(),
+        }
+    }
+
+    /**
+     * Serialize a command and queue it for the server. The length prefix is added by
+     * the RtpCodec. Commands are written in the order they are queued.
+     * @param cmd   the command to send
+     * @return      an error if the queue is full or the connection is closed
+     */
+    pub fn send(&mut self, cmd: ClientCommand) -> Result<(), SendError> {
+        let mut pkt = Vec::new();
+        cmd.serialize(&mut Serializer::new(&mut pkt)).unwrap();
+        if pkt.len() > u16::MAX as usize {
+            error!("Can't send RTP packet because buffer is too long");
+            return Err(SendError::TooLong);
+        }
+        self.tx.try_send(pkt).map_err(|e| {
+            if e.is_full() {
+                SendError::QueueFull
+            } else {
+                SendError::Disconnected
+            }
+        })
+    }
+
+    /**
+     * Called when the connection is established, including after a reconnection.
+     * Negotiate the protocol version, then send the player details again and go
+     * back to the room we were in.
+     */
+    pub fn resume_session(&mut self) {
+        self.heartbeat.reset();
+        self.handshake = Handshake::Pending;
+        self.prediction.reset();
+        let hello = ClientCommand::Hello {
+            version: PROTOCOL_VERSION,
+            min_version: MIN_PROTOCOL_VERSION,
+            capabilities: CAPABILITIES.iter().map(|c| c.to_string()).collect(),
+        };
+        if let Err(e) = self.send(hello) {
+            warn!("Can't send hello: {}", e);
+        }
+        if let Some(name) = self.player_name.clone() {
+            if let Err(e) = self.send(ClientCommand::Player { name }) {
+                warn!("Can't send player details: {}", e);
+            }
+        }
+        if let Some(room) = self.current_room_id {
+            info!("Rejoining room {}", room);
+            if let Err(e) = self.send(ClientCommand::Join { room }) {
+                warn!("Can't rejoin room {}: {}", room, e);
+            }
+        }
+    }
+
+    /**
+     * Send a new heartbeat to the server
+     * @return      an error if the ping can't be queued
+     */
+    pub fn ping(&mut self) -> Result<(), SendError> {
+        let id = self.heartbeat.ping(Instant::now());
+        self.send(ClientCommand::Ping { id })
+    }
+
+    /**
+     * Called when the server didn't answer the hello in time. Servers older than the
+     * handshake ignore it, so they are used with protocol version 1 and no capability.
+     */
+    pub fn handshake_timeout(&mut self) {
+        if self.handshake == Handshake::Pending {
+            warn!("No welcome from the server, assuming protocol version {}", MIN_PROTOCOL_VERSION);
+            self.handshake = Handshake::Done { version: MIN_PROTOCOL_VERSION, capabilities: Vec::new() };
+        }
+    }
+
+    fn welcome(&mut self, msg: WelcomeMsg) {
+        self.handshake = if msg.is_compatible() {
+            info!("Protocol version {} (server capabilities: {:?})", msg.version, msg.capabilities);
+            Handshake::Done { version: msg.version, capabilities: msg.capabilities }
+        } else {
+            let reason = format!("the server speaks protocol version {}, this client supports versions {} to {}",
+                                 msg.version, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION);
+            error!("Incompatible server: {}", reason);
+            Handshake::Incompatible(reason)
+        };
+    }
+
+    fn game_map(&mut self, msg_type: &'static str) -> Result<&mut Map, MsgError> {
+        self.map.as_mut().ok_or(MsgError::NoMap(msg_type))
+    }
+
+    /**
+     * @param map       the current map
+     * @param msg_type  the message, for the error
+     * @param x         column of the square
+     * @param y         row of the square
+     * @return          the index of the square or an error if it is not in the map
+     */
+    fn square(map: &Map, msg_type: &'static str, x: f64, y: f64) -> Result<usize, MsgError> {
+        // Also rejects NaN
+        if !(x >= 0.0 && y >= 0.0) {
+            return Err(MsgError::OutOfMap { msg_type, x, y });
+        }
+        map.index(x as usize, y as usize).ok_or(MsgError::OutOfMap { msg_type, x, y })
+    }
+
+    fn identify(&mut self, msg: PlayerIdentity) -> Result<(), MsgError> {
+        // Without a map the id is checked by the users of linked_id
+        if let Some(map) = &self.map {
+            if !usize::try_from(msg.id).is_ok_and(|id| id < map.players.len()) {
+                return Err(MsgError::UnknownPlayer { msg_type: "player_identity", id: msg.id as i64 });
+            }
+        }
+        self.linked_id = Some(msg.id);
+        Ok(())
+    }
+
+    fn move_player(&mut self, diff: PlayerMove) -> Result<(), MsgError> {
+        let predicts = self.predicts();
+        let map = self.map.as_mut().ok_or(MsgError::NoMap("player_move_diff"))?;
+        let unknown = MsgError::UnknownPlayer { msg_type: "player_move_diff", id: diff.id as i64 };
+        let id = usize::try_from(diff.id).ok()
+            .filter(|id| *id < map.players.len())
+            .ok_or(unknown)?;
+        match diff.seq {
+            Some(ack) if predicts && self.linked_id == Some(id as u64) => {
+                self.prediction.reconcile(map, id, diff.x, diff.y, ack);
+            },
+            _ => {
+                map.players[id].x = diff.x;
+                map.players[id].y = diff.y;
+            },
+        }
+        self.interpolation.push(id, Sample { time: Instant::now(), x: diff.x, y: diff.y });
+        Ok(())
+    }
+
+    fn move_bomb(&mut self, diff: BombMove) -> Result<(), MsgError> {
+        let map = self.game_map("bomb_move_diff")?;
+        let old = Client::square(map, "bomb_move_diff", diff.old_x as f64, diff.old_y as f64)?;
+        let new = Client::square(map, "bomb_move_diff", diff.x as f64, diff.y as f64)?;
+        if old == new {
+            // There is no item animation for now
+            return Ok(());
+        }
+        match map.items[old] {
+            Some(ref item) if item.name() == "Bomb" => {},
+            _ => return Ok(()),
+        }
+        map.items[old] = None;
+        map.items[new] = Some(Box::new(bomb::BombItem {}));
+        let (w, x, y) = (map.w, diff.x as usize, diff.y as usize);
+        for bomb in self.bombs.iter_mut().filter(|b| b.x + b.y * w == old) {
+            bomb.x = x;
+            bomb.y = y;
+        }
+        Ok(())
+    }
+
+    fn player_put_bomb(&mut self, diff: PlayerPutBomb) -> Result<(), MsgError> {
+        let map = self.game_map("player_put_bomb_diff")?;
+        let idx = Client::square(map, "player_put_bomb_diff", diff.x as f64, diff.y as f64)?;
+        map.items[idx] = Some(Box::new(bomb::BombItem {}));
+        // The owner may be unknown to old servers, the blast is at least one square
+        let radius = usize::try_from(diff.id).ok()
+            .and_then(|id| map.players.get(id))
+            .map_or(1, |player| player.radius);
+        self.bombs.push(LiveBomb { x: diff.x, y: diff.y, owner: diff.id, radius, placed: Instant::now() });
+        Ok(())
+    }
+
+    fn bomb_explode(&mut self, diff: BombExplode) -> Result<(), MsgError> {
+        let map = self.game_map("bomb_explode")?;
+        let idx = Client::square(map, "bomb_explode", diff.w as f64, diff.h as f64)?;
+        let (x, y) = (diff.w as usize, diff.h as usize);
+        let radius = match self.bombs.iter().position(|b| b.x == x && b.y == y) {
+            Some(pos) => self.bombs.remove(pos).radius,
+            None => 1,
+        };
+        let now = Instant::now();
+        self.flames.retain(|flame| flame.until > now);
+        if let Some(map) = self.map.as_mut() {
+            let hit = blast(map, (x, y), radius);
+            self.flames.extend(hit.into_iter().map(|(x, y)| Flame { x, y, until: now + FLAME_TIME }));
+            map.items[idx] = None;
+        }
+        Ok(())
+    }
+
+    fn create_item(&mut self, diff: CreateItem) -> Result<(), MsgError> {
+        let map = self.game_map("create_item")?;
+        let idx = Client::square(map, "create_item", diff.w as f64, diff.h as f64)?;
+        let bomb = diff.item.as_ref().is_some_and(|item| item.name() == "Bomb");
+        map.items[idx] = diff.item;
+        if !bomb {
+            self.forget_bomb(diff.w as usize, diff.h as usize);
+        }
+        Ok(())
+    }
+
+    fn destroy_item(&mut self, diff: DestroyItem) -> Result<(), MsgError> {
+        let map = self.game_map("destroy_item")?;
+        let idx = Client::square(map, "destroy_item", diff.w as f64, diff.h as f64)?;
+        map.items[idx] = None;
+        self.forget_bomb(diff.w as usize, diff.h as usize);
+        Ok(())
+    }
+
+    /**
+     * Stop tracking the bomb of a square which doesn't hold a bomb anymore
+     * @param x     column of the square
+     * @param y     row of the square
+     */
+    fn forget_bomb(&mut self, x: usize, y: usize) {
+        self.bombs.retain(|b| b.x != x || b.y != y);
+    }
+
+    fn player_die(&mut self, diff: PlayerDie) -> Result<(), MsgError> {
+        let map = self.game_map("player_die")?;
+        let unknown = MsgError::UnknownPlayer { msg_type: "player_die", id: diff.id as i64 };
+        let player = usize::try_from(diff.id).ok()
+            .and_then(|id| map.players.get_mut(id))
+            .ok_or(unknown)?;
+        player.dead = true;
+        if let Some(round) = &mut self.round {
+            round.player_died(diff.id, Instant::now());
+        }
+        if self.linked_id == Some(diff.id) {
+            info!("You died");
+        }
+        Ok(())
+    }
+
+    fn game_over(&mut self, msg: GameOverMsg) -> Result<(), MsgError> {
+        let players = self.game_map("game_over")?.players.len() as u64;
+        let round = self.round.get_or_insert_with(|| Round::new(Instant::now()));
+        round.finish(players, msg.winner, Instant::now());
+        Ok(())
+    }
+
+    /**
+     * @return if our player is dead in the current game
+     */
+    pub fn is_dead(&self) -> bool {
+        match (&self.round, self.linked_id) {
+            (Some(round), Some(id)) => round.died_at(id).is_some(),
+            _ => false,
+        }
+    }
+
+    /**
+     * Forget the current game, after its result was shown
+     */
+    pub fn leave_game(&mut self) {
+        self.map = None;
+        self.linked_id = None;
+        self.round = None;
+        self.bombs.clear();
+        self.flames.clear();
+        self.player_names.clear();
+        self.effects.clear();
+        self.interpolation.reset();
+        self.prediction.reset();
+    }
+
+    fn update_player(&mut self, diff: PlayerUpdate) -> Result<(), MsgError> {
+        let map = self.game_map("player_update_diff")?;
+        let unknown = MsgError::UnknownPlayer { msg_type: "player_update_diff", id: diff.id as i64 };
+        let id = usize::try_from(diff.id).ok()
+            .filter(|id| *id < map.players.len())
+            .ok_or(unknown)?;
+        let player = &mut map.players[id];
+        player.radius = diff.radius;
+        player.speed_factor = diff.speed_factor;
+        player.bomb = diff.bomb;
+        let now = Instant::now();
+        self.effects[id] = diff.effects.into_iter().map(|effect| PlayerEffect {
+            // Too long to be represented: until the end of the game
+            end: effect.remaining_ms.and_then(|ms| now.checked_add(Duration::from_millis(ms))),
+            malus: effect.malus,
+            bonus: effect.bonus,
+        }).collect();
+        Ok(())
+    }
+
+    fn update_square(&mut self, diff: UpdateSquare) -> Result<(), MsgError> {
+        let map = self.game_map("update_square")?;
+        let idx = Client::square(map, "update_square", diff.x as f64, diff.y as f64)?;
+        map.squares[idx].sq_type = diff.square;
+        Ok(())
+    }
+
+    fn new_map(&mut self, msg: MapMsg) -> Result<(), MsgError> {
+        if !msg.map.is_consistent() {
+            return Err(MsgError::Malformed(format!("Inconsistent {}x{} map", msg.map.w, msg.map.h)));
+        }
+        self.effects = vec![Vec::new(); msg.map.players.len()];
+        self.player_names = msg.names;
+        self.round = Some(Round::new(Instant::now()));
+        self.bombs.clear();
+        self.flames.clear();
+        self.map = Some(msg.map);
+        self.interpolation.reset();
+        self.prediction.reset();
+        Ok(())
+    }
+
+    /**
+     * Where to render a player: between the last positions received
+     * @param id    the player
+     * @param now   the time of the frame
+     * @return      the position, None if the player is not in the map
+     */
+    pub fn player_position(&self, id: usize, now: Instant) -> Option<(f32, f32)> {
+        let player = self.map.as_ref()?.players.get(id)?;
+        if self.predicts() && self.linked_id == Some(id as u64) {
+            // Already ahead of the server
+            return Some((player.x, player.y));
+        }
+        Some(self.interpolation.position(id, now).unwrap_or((player.x, player.y)))
+    }
+
+    /**
+     * Move our player: predicted locally when the server acknowledges moves
+     * @param direction where to go
+     * @return          an error if the move can't be queued
+     */
+    pub fn move_to(&mut self, direction: Direction) -> Result<(), SendError> {
+        let mut seq = None;
+        if self.predicts() {
+            let id = self.linked_id.and_then(|id| usize::try_from(id).ok());
+            if let (Some(id), Some(map)) = (id, self.map.as_mut()) {
+                seq = Some(self.prediction.input(map, id, direction));
+            }
+        }
+        self.send(ClientCommand::Move { direction, seq })
+    }
+
+    /**
+     * @return if the server acknowledges our moves, so they can be predicted
+     */
+    pub fn predicts(&self) -> bool {
+        matches!(self.handshake, Handshake::Done { version, .. } if version >= 2) && self.supports("input_sequence")
+    }
+
+    /**
+     * @param capability    an optional feature, see CAPABILITIES
+     * @return              if the server announced it in its welcome
+     */
+    pub fn supports(&self, capability: &str) -> bool {
+        match &self.handshake {
+            Handshake::Done { capabilities, .. } => capabilities.iter().any(|c| c == capability),
+            _ => false,
+        }
+    }
+
+    fn negotiated(&self, capability: &'static str) -> Result<(), MsgError> {
+        if self.supports(capability) {
+            Ok(())
+        } else {
+            Err(MsgError::NotNegotiated(capability))
+        }
+    }
+
+    /**
+     * Decode a packet from the server and update the state. Invalid packets are
+     * logged, counted in protocol_errors and ignored.
+     * @param pkt   the msgpack payload (without the length prefix)
+     * @return      why the packet was ignored
+     */
+    pub fn parse_rtp(&mut self, pkt: Vec<u8>) -> Result<(), MsgError> {
+        let result = self.apply(&pkt);
+        if let Err(ref e) = result {
+            self.protocol_errors += 1;
+            warn!("Ignoring packet: {}", e);
+        }
+        result
+    }
+
+    /**
+     * Handle bytes received from the server, as they come from the socket. Complete
+     * packets are parsed, the rest is kept for the next call.
+     * @param data  the received bytes (length prefixed packets)
+     */
+    pub fn process_rx(&mut self, data: &[u8]) {
+        self.rx_buffer.extend_from_slice(data);
+        while let Ok(Some(pkt)) = RtpCodec.decode(&mut self.rx_buffer) {
+            let _ = self.parse_rtp(pkt);
+        }
+    }
+
+    fn apply(&mut self, pkt: &[u8]) -> Result<(), MsgError> {
+        let msg = ServerMsg::decode(pkt)?;
+        info!("RX {}", msg.msg_type());
+        match msg {
+            ServerMsg::Map(msg) => self.new_map(msg),
+            ServerMsg::PlayerMove(msg) => self.move_player(msg),
+            ServerMsg::BombMove(msg) => self.move_bomb(msg),
+            ServerMsg::PlayerPutBomb(msg) => self.player_put_bomb(msg),
+            ServerMsg::PlayerDie(msg) => self.player_die(msg),
+            ServerMsg::BombExplode(msg) => self.bomb_explode(msg),
+            ServerMsg::DestroyItem(msg) => self.destroy_item(msg),
+            ServerMsg::CreateItem(msg) => self.create_item(msg),
+            ServerMsg::PlayerIdentity(msg) => self.identify(msg),
+            ServerMsg::UpdateSquare(msg) => self.update_square(msg),
+            ServerMsg::PlayerUpdate(msg) => {
+                self.negotiated("player_update")?;
+                self.update_player(msg)
+            },
+            ServerMsg::GameOver(msg) => {
+                self.negotiated("game_over")?;
+                self.game_over(msg)
+            },
+            ServerMsg::Welcome(msg) => {
+                self.welcome(msg);
+                Ok(())
+            },
+            ServerMsg::Pong(msg) => {
+                if self.heartbeat.pong(msg.id, Instant::now()).is_none() {
+                    debug!("Ignoring pong {}", msg.id);
+                }
+                Ok(())
+            },
+            ServerMsg::Joined(msg) => {
+                if msg.success {
+                    self.current_room_id = Some(msg.room);
+                }
+                Ok(())
+            },
+        }
+    }
+}
@@ -29,6 +29,9 @@ use super::super::utils::MapPlayer;
 use super::{Walkable, Item};
 use std::any::Any;
 
+/**
+ * A bomb waiting to explode, see LiveBomb for its owner and radius
+ */
 #[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
 pub struct BombItem;
 
@@ -54,6 +57,6 @@ impl Item for BombItem {
     }
 
     fn box_clone(&self) -> Box<dyn Item> {
-        Box::new((*self).clone())
+        Box::new(*self)
     }
 }
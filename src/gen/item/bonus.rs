@@ -33,13 +33,22 @@ use rand::{
 };
 use std::any::Any;
 
+/**
+ * An item improving the player who takes it
+ */
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 pub enum Bonus {
+    /// Flames of the bombs go further
     ImproveBombRadius,
+    /// Bombs can be punched over the other squares
     PunchBombs,
+    /// The player moves faster
     ImproveSpeed,
+    /// Bombs can be pushed away
     RepelBombs,
+    /// One more bomb can be put at the same time
     MoreBombs,
+    /// Bonus of a server extension, by name
     Custom(String)
 }
 
@@ -29,6 +29,9 @@ use super::super::utils::MapPlayer;
 use super::{Walkable, Item};
 use std::any::Any;
 
+/**
+ * A box blocking the way until it is destroyed by a bomb
+ */
 #[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
 pub struct DestructibleBox;
 
@@ -53,6 +56,6 @@ impl Item for DestructibleBox {
     }
 
     fn box_clone(&self) -> Box<dyn Item> {
-        Box::new((*self).clone())
+        Box::new(*self)
     }
 }
\ No newline at end of file
@@ -33,13 +33,22 @@ use rand::{
 };
 use std::any::Any;
 
+/**
+ * An item hindering the player who takes it, for a while
+ */
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 pub enum Malus {
+    /// The player moves slower
     Slow,
+    /// The player moves too fast
     UltraFast,
+    /// Bombs of the player explode sooner
     SpeedBomb,
+    /// The player puts bombs without asking
     DropBombs,
+    /// Directions are swapped
     InvertedControls,
+    /// Malus of a server extension, by name
     Custom(String)
 }
 
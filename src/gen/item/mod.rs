@@ -32,21 +32,47 @@ use std::fmt::Debug;
 use serde::Serialize;
 use rmps::Serializer;
 
+/**
+ * How an item interacts with the players and the explosions
+ */
 pub trait Walkable {
+    /**
+     * @param p     a player
+     * @param pos   position of the item
+     * @return      if the player can walk on the item
+     */
     fn walkable(&self, p: &MapPlayer, pos: &(usize, usize)) -> bool;
 
+    /**
+     * @param pos       position of the item
+     * @param bomb_pos  position of the exploding bomb
+     * @return          if the item stops the flames, and if it is destroyed
+     */
     fn explode_event(&self, pos: &(usize, usize), bomb_pos: &(usize, usize)) -> (bool /* block */, bool /* destroy item */);
 }
 
+/**
+ * Something on a square of the map, serialized with its type name
+ */
 #[typetag::serde]
 pub trait Item: Walkable + Sync + Send + Debug {
+    /**
+     * @return  name of the item type
+     */
     fn name(&self) -> String;
 
+    /**
+     * @return  the item, to downcast it to its type
+     */
     fn as_any(&self) -> &dyn Any;
 
+    /**
+     * @return  a copy of the item
+     */
     fn box_clone(&self) -> Box<dyn Item>;
 }
 
+/// An item of the map
 pub type InteractiveItem = Box<dyn Item>;
 
 /**
@@ -69,11 +95,15 @@ impl Clone for Box<dyn Item>
     }
 }
 
+/// Bombs put by the players
 pub mod bomb;
 pub use bomb::BombItem;
+/// Items improving the players
 pub mod bonus;
 pub use bonus::Bonus;
+/// Boxes destroyed by the explosions
 pub mod destructiblebox;
 pub use destructiblebox::DestructibleBox;
+/// Items hindering the players
 pub mod malus;
 pub use malus::Malus;
@@ -31,15 +31,23 @@ use rand::Rng;
 use super::utils::{Direction, MapPlayer, Square, SquareType};
 use super::item::*;
 
+/// Largest width or height of a map accepted from the server
+pub const MAX_MAP_SIDE: usize = 255;
+
 /**
  * Represent a map for a game
  */
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct Map {
+    /// Width, in squares
     pub w: usize,
+    /// Height, in squares
     pub h: usize,
+    /// Row by row, w * h squares
     pub squares: Vec<Square>,
+    /// By player id
     pub players: Vec<MapPlayer>,
+    /// Item on each square, like squares
     pub items: Vec<Option<InteractiveItem>>,
 }
 
@@ -58,7 +66,7 @@ impl Map {
         if h < 11 {
             h = 11;
         }
-        let size = (w * h) as usize;
+        let size = w * h;
         let mut squares = Vec::with_capacity(size);
         let mut items: Vec<Option<InteractiveItem>> = Vec::with_capacity(size);
         let mut players = Vec::new();
@@ -72,7 +80,7 @@ impl Map {
             }
 
             let add_box: u8 = rng.gen();
-            if add_box % 3 != 0 && sq_type == SquareType::Empty {
+            if !add_box.is_multiple_of(3) && sq_type == SquareType::Empty {
                 items.push(Some(Box::new(DestructibleBox {})));
             } else {
                 items.push(None);
@@ -132,6 +140,29 @@ impl Map {
         res
     }
 
+    /**
+     * @param x     column of the square
+     * @param y     row of the square
+     * @return      the index of the square in squares and items, None if outside the map
+     */
+    pub fn index(&self, x: usize, y: usize) -> Option<usize> {
+        if x < self.w && y < self.h {
+            Some(x + y * self.w)
+        } else {
+            None
+        }
+    }
+
+    /**
+     * @return if the dimensions are between 1 and MAX_MAP_SIDE and there is one
+     *         square and one item slot per position
+     */
+    pub fn is_consistent(&self) -> bool {
+        let valid_side = |side: usize| side > 0 && side <= MAX_MAP_SIDE;
+        valid_side(self.w) && valid_side(self.h)
+            && self.squares.len() == self.w * self.h && self.items.len() == self.w * self.h
+    }
+
     /**
      * Modify the map till all players can safely play
      * @todo REDO THIS DIRTY AND HACKY THING
@@ -157,10 +188,10 @@ impl Map {
                     direction_tested = 0;
                     prefer_n = rng.gen();
                     prefer_w = rng.gen();
-                    let current = safe[safe_idx].clone();
+                    let current = safe[safe_idx];
                     safe_idx += 1;
                     if safe_idx >= safe.len() {
-                        if destroyable.len() == 0 {
+                        if destroyable.is_empty() {
                             break;
                         }
                         let new_safe = destroyable.pop().unwrap();
@@ -211,7 +242,7 @@ impl Map {
                     prefer_n = !prefer_n;
                     continue;
                 }
-                if safe.iter().find(|&&x| x == (to_test_x as usize, to_test_y as usize)) != None {
+                if safe.contains(&(to_test_x as usize, to_test_y as usize)) {
                     if check_x {
                         inc_x = 0;
                         prefer_w = !prefer_w;
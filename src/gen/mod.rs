@@ -25,8 +25,13 @@
  * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  **/
 
-mod tlsclient;
-pub mod msg;
-pub mod diff_msg;
+/// The map of a game
+pub mod map;
+/// Items which can be on a square (bombs, bonus, boxes...)
+// The registry generated by typetag implements traits from inside a const
+#[allow(non_local_definitions)]
+pub mod item;
+/// Players, squares, directions and effects
+pub mod utils;
 
-pub use tlsclient::{ ConnectionState, TlsClient, TlsClientConfig };
\ No newline at end of file
+pub use map::{ Map, MAX_MAP_SIDE };
\ No newline at end of file
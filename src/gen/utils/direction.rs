@@ -30,11 +30,18 @@ use rand::{
     Rng,
 };
 
+/**
+ * A side of a square, or a move
+ */
 #[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
 pub enum Direction {
+    /// Up
     North,
+    /// Down
     South,
+    /// Left
     West,
+    /// Right
     East,
 }
 
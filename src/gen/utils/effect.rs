@@ -26,14 +26,17 @@
  **/
 
 use std::time::Instant;
-use crate::bomber::gen::item::{Bonus, Malus};
+use crate::gen::item::{Bonus, Malus};
 
 /**
  * Represent a player effet
  */
 #[derive(Clone)]
 pub struct PlayerEffect {
+    /// None if the effect lasts until the end of the game
     pub end: Option<Instant>,
+    /// Malus causing the effect, if any
     pub malus: Option<Malus>,
+    /// Bonus causing the effect, if any
     pub bonus: Option<Bonus>,
 }
\ No newline at end of file
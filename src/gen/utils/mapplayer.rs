@@ -30,10 +30,16 @@
  */
 #[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
 pub struct MapPlayer {
+    /// Column, the middle of a square is at .5
     pub x: f32,
+    /// Row, the middle of a square is at .5
     pub y: f32,
+    /// Squares reached by the flames of the bombs of the player
     pub radius: u32,
+    /// Speed, 1000 is the default speed
     pub speed_factor: u32,
+    /// Bombs the player can put at the same time
     pub bomb: u32,
+    /// If the player was killed by an explosion
     pub dead: bool,
 }
\ No newline at end of file
@@ -25,10 +25,15 @@
  * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  **/
 
+/// Sides of the squares and moves
 pub mod direction;
+/// Players on the map
 pub mod mapplayer;
+/// Shapes of the explosions
 pub mod shape;
+/// Squares of the map
 pub mod square;
+/// Effects of the items on the players
 pub mod effect;
 
 pub use direction::Direction;
@@ -30,7 +30,10 @@
  */
 #[derive(Clone)]
 pub enum Shape {
+    /// Lines in the 4 directions
     Cross,
+    /// Squares around the center
     Square,
+    /// Squares in the radius around the center
     Circle
 }
\ No newline at end of file
@@ -33,12 +33,19 @@ use rand::{
 };
 
 
+/**
+ * Ground of a square
+ */
 #[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
 pub enum SquareType {
+    /// Can't be walked on, lets the flames through
     Water,
+    /// Can be walked on
     Empty,
+    /// A wall on this side of the square
     Wall(Direction),
-    Block, /* Not randomly generated */
+    /// Can't be walked on, stops the flames. Not randomly generated
+    Block,
 }
 
 impl Distribution<SquareType> for Standard {
@@ -84,7 +91,11 @@ impl Walkable for SquareType {
     }
 }
 
+/**
+ * A square of the map
+ */
 #[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
 pub struct Square {
+    /// Ground of the square
     pub sq_type: SquareType
 }
\ No newline at end of file
@@ -0,0 +1,179 @@
+/**
+ * Copyright (c) 2019, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+
+use std::collections::VecDeque;
+use std::time::{ Duration, Instant };
+
+/// How far in the past other players are shown, so there is usually a newer
+/// position to move towards
+pub const INTERPOLATION_DELAY: Duration = Duration::from_millis(100);
+
+/// Positions kept per player
+const MAX_SAMPLES: usize = 32;
+
+/**
+ * Position of a player received at a given time
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sample {
+    /// When the position was received
+    pub time: Instant,
+    /// Column of the player
+    pub x: f32,
+    /// Row of the player
+    pub y: f32,
+}
+
+/**
+ * Keep the last positions of each player to render smooth moves between the
+ * server updates instead of jumping to each new position.
+ */
+#[derive(Debug)]
+pub struct Interpolation {
+    delay: Duration,
+    players: Vec<VecDeque<Sample>>, // Oldest first
+}
+
+impl Interpolation {
+    /**
+     * @param delay     how far in the past positions are rendered
+     */
+    pub fn new(delay: Duration) -> Interpolation {
+        Interpolation {
+            delay,
+            players: Vec::new(),
+        }
+    }
+
+    /**
+     * Forget every position, for a new game
+     */
+    pub fn reset(&mut self) {
+        self.players.clear();
+    }
+
+    /**
+     * Add a position received from the server
+     * @param id        the player
+     * @param sample    the position and when it was received
+     */
+    pub fn push(&mut self, id: usize, sample: Sample) {
+        if self.players.len() <= id {
+            self.players.resize_with(id + 1, VecDeque::new);
+        }
+        let samples = &mut self.players[id];
+        // Samples must stay ordered
+        if samples.back().is_some_and(|last| last.time > sample.time) {
+            samples.clear();
+        }
+        if samples.len() == MAX_SAMPLES {
+            samples.pop_front();
+        }
+        samples.push_back(sample);
+    }
+
+    /**
+     * @param id    the player
+     * @param now   the time of the frame to render
+     * @return      the position to render, None if no position was received
+     */
+    pub fn position(&self, id: usize, now: Instant) -> Option<(f32, f32)> {
+        let samples = self.players.get(id)?;
+        let first = samples.front()?;
+        let target = match now.checked_sub(self.delay) {
+            Some(target) if target > first.time => target,
+            _ => return Some((first.x, first.y)),
+        };
+        // First sample after the target, the previous one is before
+        let next = match samples.iter().position(|s| s.time > target) {
+            Some(next) => next,
+            None => {
+                let last = samples.back()?;
+                return Some((last.x, last.y));
+            },
+        };
+        let (from, to) = (samples[next - 1], samples[next]);
+        let ratio = (target - from.time).as_secs_f32() / (to.time - from.time).as_secs_f32();
+        Some((from.x + (to.x - from.x) * ratio, from.y + (to.y - from.y) * ratio))
+    }
+}
+
+impl Default for Interpolation {
+    fn default() -> Interpolation {
+        Interpolation::new(INTERPOLATION_DELAY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(start: Instant, ms: u64, x: f32, y: f32) -> Sample {
+        Sample { time: start + Duration::from_millis(ms), x, y }
+    }
+
+    #[test]
+    fn no_sample_no_position() {
+        let interpolation = Interpolation::default();
+        assert_eq!(interpolation.position(0, Instant::now()), None);
+    }
+
+    #[test]
+    fn position_between_samples() {
+        let start = Instant::now();
+        let mut interpolation = Interpolation::new(Duration::from_millis(100));
+        interpolation.push(1, sample(start, 0, 1.0, 1.0));
+        interpolation.push(1, sample(start, 100, 2.0, 3.0));
+        assert_eq!(interpolation.position(0, start), None);
+        // Rendered 100ms in the past
+        assert_eq!(interpolation.position(1, start + Duration::from_millis(100)), Some((1.0, 1.0)));
+        assert_eq!(interpolation.position(1, start + Duration::from_millis(150)), Some((1.5, 2.0)));
+        assert_eq!(interpolation.position(1, start + Duration::from_millis(200)), Some((2.0, 3.0)));
+    }
+
+    #[test]
+    fn clamped_to_known_samples() {
+        let start = Instant::now();
+        let mut interpolation = Interpolation::new(Duration::from_millis(100));
+        interpolation.push(0, sample(start, 50, 4.0, 4.0));
+        interpolation.push(0, sample(start, 60, 5.0, 4.0));
+        assert_eq!(interpolation.position(0, start), Some((4.0, 4.0)));
+        assert_eq!(interpolation.position(0, start + Duration::from_secs(10)), Some((5.0, 4.0)));
+    }
+
+    #[test]
+    fn samples_are_bounded_and_reset() {
+        let start = Instant::now();
+        let mut interpolation = Interpolation::default();
+        for i in 0..100 {
+            interpolation.push(0, sample(start, i, i as f32, 0.0));
+        }
+        assert_eq!(interpolation.players[0].len(), MAX_SAMPLES);
+        interpolation.reset();
+        assert_eq!(interpolation.position(0, start), None);
+    }
+}
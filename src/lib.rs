@@ -0,0 +1,128 @@
+/*
+ * Copyright (c) 2019, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+
+//! Client side of the Bomberust protocol, to write frontends, bots and tools.
+//!
+//! - `net` connects to a server (TLS, length prefixed msgpack packets) and defines the messages
+//! - `gen` contains the game state: the map, squares, players and items
+//! - `Client` keeps the state of the current session up to date from the server messages
+//! - `MapWidget` draws the game in a tui terminal, with glyphs from a theme (`tui` feature, on by
+//!   default; bots can use `default-features = false` to leave out tui and unicode-width)
+//!
+//! ```no_run
+//! use bomberust_client::{ Client, OUTBOUND_QUEUE_SIZE };
+//! use bomberust_client::net::{ ConnectionState, TlsClient, TlsClientConfig, HANDSHAKE_TIMEOUT };
+//! use bomberust_client::net::msg::ClientCommand;
+//! use futures::sync::mpsc;
+//! use std::sync::{ Arc, Mutex };
+//! use std::thread;
+//!
+//! let (tx, rx) = mpsc::channel(OUTBOUND_QUEUE_SIZE);
+//! let mut client = Client::new(tx);
+//! client.player_name = Some(String::from("bot"));
+//! let config = TlsClientConfig {
+//!     server_state: Arc::new(Mutex::new(Some(ConnectionState::Connecting))),
+//!     addr: String::from("bomberust.example.org"),
+//!     server_name: String::new(),
+//!     cert: String::new(),
+//!     client_cert: String::new(),
+//!     client_key: String::new(),
+//!     trust_on_first_use: false,
+//!     fingerprint: String::new(),
+//!     reconnect: Default::default(),
+//!     heartbeat: Default::default(),
+//!     handshake_timeout: HANDSHAKE_TIMEOUT,
+//!     client: Arc::new(Mutex::new(client)),
+//! };
+//! let client = config.client.clone();
+//! thread::spawn(move || TlsClient::start(&config, rx));
+//!
+//! client.lock().unwrap().send(ClientCommand::Create).unwrap();
+//! ```
+//!
+//! ## Protocol extensions
+//!
+//! Some messages are not part of the base protocol. The client announces them in the
+//! `capabilities` of its hello and the server only sends the ones listed in its welcome;
+//! `Client` rejects the others with `MsgError::NotNegotiated`. Like every message, they
+//! are msgpack arrays holding the `msg_type` then the fields in order:
+//!
+//! - `player_update_diff` (capability `player_update`): `[msg_type, id, radius, speed_factor,
+//!   bomb, effects]`, sent when the stats of a player change. `effects` lists every active
+//!   effect of the player as `[bonus, malus, remaining_ms]`, replacing the previous ones.
+//!   `bonus` and `malus` are nil or the index of a `Bonus` or `Malus` variant as `{index: nil}`,
+//!   `remaining_ms` is nil for an effect lasting until the end of the game
+//! - `game_over` (capability `game_over`): `[msg_type, winner]`, sent when the game ends.
+//!   `winner` is the id of the winning player, or nil for a draw or to let the client pick
+//!   the last survivor. The players stay in the room
+
+#![warn(missing_docs)]
+extern crate bytes;
+extern crate futures;
+#[macro_use]
+extern crate log;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate ring;
+extern crate rmp_serde as rmps;
+extern crate tokio;
+extern crate tokio_rustls;
+#[cfg(feature = "tui")]
+extern crate tui;
+extern crate typetag;
+#[cfg(feature = "tui")]
+extern crate unicode_width;
+extern crate webpki;
+extern crate webpki_roots;
+
+mod bombs;
+mod client;
+/// Maps for tests, benchmarks and fuzzing
+#[cfg(any(test, feature = "fixtures"))]
+pub mod fixtures;
+mod interpolation;
+mod prediction;
+#[cfg(feature = "tui")]
+mod render;
+mod round;
+#[cfg(feature = "tui")]
+mod theme;
+/// Game state shared with the server: map, squares, players, items
+pub mod gen;
+/// Connection to the server and protocol messages
+pub mod net;
+
+pub use bombs::{ blast, danger_zone, Flame, LiveBomb, FLAME_TIME, FUSE_TIME };
+pub use client::{ Client, Handshake, SendError, OUTBOUND_QUEUE_SIZE };
+pub use interpolation::{ Interpolation, Sample, INTERPOLATION_DELAY };
+pub use prediction::{ predict_move, Prediction, MOVE_STEP };
+#[cfg(feature = "tui")]
+pub use render::{ Glyph, Glyphs, ItemKind, MapWidget, DANGER_SOON, THEMES };
+pub use round::{ Round, RoundResult };
+#[cfg(feature = "tui")]
+pub use theme::{ load_theme, parse_color, parse_theme, Theme, ThemeError };
@@ -26,6 +26,7 @@
  **/
 
 
+extern crate bomberust_client;
 extern crate env_logger;
 extern crate futures;
 #[macro_use]
@@ -33,25 +34,11 @@ extern crate log;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
-extern crate rmp_serde as rmps;
-extern crate tokio;
-extern crate tokio_rustls;
 extern crate tokio_stdin_stdout;
-extern crate typetag;
-extern crate webpki;
-extern crate webpki_roots;
 
-mod bomber;
-
-use bomber::core::{Client, KeyHandler, TuiClient};
-use bomber::net::{TlsClient, TlsClientConfig};
-
-use std::sync::{Arc, Mutex};
-use std::thread;
-use tui::widgets::canvas::Line;
-use tui::widgets::canvas::Points;
-use futures::sync::mpsc;
+mod ui;
 
+use ui::TuiClient;
 
 extern crate failure;
 extern crate tui;
@@ -109,9 +96,9 @@ impl App {
     }
 }*/
 
-fn main() {
+fn main() -> Result<(), failure::Error> {
     let mut client = TuiClient::new();
-    client.render();
+    client.render()?;
     // Terminal initialization
     /*let stdout = io::stdout().into_raw_mode()?;
     let stdout = MouseTerminal::from(stdout);
@@ -228,6 +215,7 @@ fn main() {
     }
 
     Ok(())*/
+    Ok(())
 }
 
 
@@ -0,0 +1,253 @@
+/**
+ * Copyright (c) 2019, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+
+use std::fmt;
+use std::net::{ IpAddr, Ipv6Addr, SocketAddr, ToSocketAddrs };
+use std::str::FromStr;
+
+/// Port used when the address doesn't contain one
+pub const DEFAULT_PORT: u16 = 2542;
+
+/**
+ * Address of a server as written by the user: host[:port], where host is a DNS name,
+ * an IPv4 address or an IPv6 address (between brackets if a port is given)
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct ServerAddress {
+    /// DNS name or IP address, without brackets
+    pub host: String,
+    /// DEFAULT_PORT if not given
+    pub port: u16,
+}
+
+/**
+ * Why an address can't be used
+ */
+#[derive(Debug, PartialEq)]
+pub enum AddressError {
+    /// Nothing was written
+    Empty,
+    /// The host is not a DNS name or an IP address
+    InvalidHost(String),
+    /// The port is not a number from 1 to 65535
+    InvalidPort(String),
+    /// Host and reason
+    Unresolved(String, String),
+    /// The certificate can only be verified against a DNS name, not this IP address
+    IpServerName(String),
+}
+
+impl fmt::Display for AddressError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AddressError::Empty => write!(f, "Please provide a server address"),
+            AddressError::InvalidHost(host) => write!(f, "Incorrect server host: {}", host),
+            AddressError::InvalidPort(port) => write!(f, "Incorrect server port: {}", port),
+            AddressError::Unresolved(host, e) => write!(f, "Unable to resolve {}: {}", host, e),
+            AddressError::IpServerName(ip) => write!(
+                f, "{} is an IP address. Please enter the name in the server certificate as server name", ip
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AddressError {}
+
+impl ServerAddress {
+    /**
+     * Parse an address without resolving it
+     * @param addr  host, host:port, [ipv6]:port or ipv6
+     * @return      the parsed address
+     */
+    pub fn parse(addr: &str) -> Result<ServerAddress, AddressError> {
+        let addr = addr.trim();
+        if addr.is_empty() {
+            return Err(AddressError::Empty);
+        }
+
+        let (host, port) = if addr.starts_with('[') {
+            let end = addr.find(']').ok_or_else(|| AddressError::InvalidHost(String::from(addr)))?;
+            let rest = &addr[end + 1..];
+            let port = if rest.is_empty() {
+                None
+            } else if let Some(port) = rest.strip_prefix(':') {
+                Some(port)
+            } else {
+                return Err(AddressError::InvalidPort(String::from(rest)));
+            };
+            let host = &addr[1..end];
+            if host.parse::<Ipv6Addr>().is_err() {
+                return Err(AddressError::InvalidHost(String::from(host)));
+            }
+            (host, port)
+        } else if addr.matches(':').count() > 1 {
+            // IPv6 literal without port
+            (addr, None)
+        } else {
+            let mut parts = addr.splitn(2, ':');
+            (parts.next().unwrap_or(""), parts.next())
+        };
+
+        let port = match port {
+            Some(port) => match port.parse::<u16>() {
+                Ok(p) if p != 0 => p,
+                _ => return Err(AddressError::InvalidPort(String::from(port))),
+            },
+            None => DEFAULT_PORT,
+        };
+
+        let valid_host = if host.contains(':') {
+            host.parse::<Ipv6Addr>().is_ok()
+        } else {
+            !host.is_empty()
+            && host.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.' || c == '_')
+        };
+        if !valid_host {
+            return Err(AddressError::InvalidHost(String::from(host)));
+        }
+
+        Ok(ServerAddress {
+            host: String::from(host),
+            port,
+        })
+    }
+
+    /**
+     * Resolve the host. This can block while the DNS is queried
+     * @return  the first socket address found for this host
+     */
+    pub fn resolve(&self) -> Result<SocketAddr, AddressError> {
+        let unresolved = |e: String| AddressError::Unresolved(self.host.clone(), e);
+        let mut addrs = (&*self.host, self.port).to_socket_addrs().map_err(|e| unresolved(e.to_string()))?;
+        addrs.next().ok_or_else(|| unresolved(String::from("no address found")))
+    }
+
+    /**
+     * Give the name the certificate of the server is verified against. Certificates are
+     * only verified for DNS names, so an IP host needs an explicit server name
+     * @param server_name   name given by the user, the host is used if empty
+     * @return              the name to verify
+     */
+    pub fn server_name(&self, server_name: &str) -> Result<String, AddressError> {
+        let name = if server_name.is_empty() { &self.host } else { server_name };
+        if name.parse::<IpAddr>().is_ok() {
+            return Err(AddressError::IpServerName(name.to_string()));
+        }
+        Ok(name.to_string())
+    }
+}
+
+impl FromStr for ServerAddress {
+    type Err = AddressError;
+
+    fn from_str(addr: &str) -> Result<ServerAddress, AddressError> {
+        ServerAddress::parse(addr)
+    }
+}
+
+impl fmt::Display for ServerAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{}:{}", self.host, self.port)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(host: &str, port: u16) -> ServerAddress {
+        ServerAddress { host: String::from(host), port }
+    }
+
+    #[test]
+    fn parse_host_and_port() {
+        assert_eq!(ServerAddress::parse("127.0.0.1:2020"), Ok(addr("127.0.0.1", 2020)));
+        assert_eq!(ServerAddress::parse("bomber.example.org:443"), Ok(addr("bomber.example.org", 443)));
+        assert_eq!(ServerAddress::parse(" localhost:1 "), Ok(addr("localhost", 1)));
+    }
+
+    #[test]
+    fn parse_default_port() {
+        assert_eq!(ServerAddress::parse("localhost"), Ok(addr("localhost", DEFAULT_PORT)));
+        assert_eq!(ServerAddress::parse("10.0.0.2"), Ok(addr("10.0.0.2", DEFAULT_PORT)));
+    }
+
+    #[test]
+    fn parse_ipv6() {
+        assert_eq!(ServerAddress::parse("[::1]:2020"), Ok(addr("::1", 2020)));
+        assert_eq!(ServerAddress::parse("[fe80::1]"), Ok(addr("fe80::1", DEFAULT_PORT)));
+        assert_eq!(ServerAddress::parse("2001:db8::42"), Ok(addr("2001:db8::42", DEFAULT_PORT)));
+        assert_eq!(addr("::1", 2020).to_string(), "[::1]:2020");
+    }
+
+    #[test]
+    fn server_name_of_ip_hosts() {
+        assert_eq!(addr("localhost", 2020).server_name(""), Ok(String::from("localhost")));
+        assert_eq!(addr("127.0.0.1", 2020).server_name("bomber.example.org"), Ok(String::from("bomber.example.org")));
+        assert_eq!(addr("127.0.0.1", 2020).server_name(""), Err(AddressError::IpServerName(String::from("127.0.0.1"))));
+        assert_eq!(addr("::1", 2020).server_name(""), Err(AddressError::IpServerName(String::from("::1"))));
+        assert_eq!(addr("localhost", 2020).server_name("10.0.0.2"), Err(AddressError::IpServerName(String::from("10.0.0.2"))));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(ServerAddress::parse(""), Err(AddressError::Empty));
+        assert_eq!(ServerAddress::parse("localhost:"), Err(AddressError::InvalidPort(String::new())));
+        assert_eq!(ServerAddress::parse("localhost:0"), Err(AddressError::InvalidPort(String::from("0"))));
+        assert_eq!(ServerAddress::parse("localhost:99999"), Err(AddressError::InvalidPort(String::from("99999"))));
+        assert_eq!(ServerAddress::parse(":2020"), Err(AddressError::InvalidHost(String::new())));
+        assert_eq!(ServerAddress::parse("[::1"), Err(AddressError::InvalidHost(String::from("[::1"))));
+        assert_eq!(ServerAddress::parse("[::1]2020"), Err(AddressError::InvalidPort(String::from("2020"))));
+        assert_eq!(ServerAddress::parse("[nope]:1"), Err(AddressError::InvalidHost(String::from("nope"))));
+        assert_eq!(ServerAddress::parse("bad host:1"), Err(AddressError::InvalidHost(String::from("bad host"))));
+    }
+
+    #[test]
+    fn resolve_literals() {
+        assert_eq!(addr("127.0.0.1", 2020).resolve(), Ok("127.0.0.1:2020".parse().unwrap()));
+        assert_eq!(addr("::1", 2020).resolve(), Ok("[::1]:2020".parse().unwrap()));
+    }
+
+    #[test]
+    fn resolve_localhost() {
+        let resolved = addr("localhost", 2020).resolve().unwrap();
+        assert!(resolved.ip().is_loopback());
+        assert_eq!(resolved.port(), 2020);
+    }
+
+    #[test]
+    fn resolve_unknown_host() {
+        match addr("unknown-host.invalid", 2020).resolve() {
+            Err(AddressError::Unresolved(host, _)) => assert_eq!(host, "unknown-host.invalid"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
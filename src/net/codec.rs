@@ -0,0 +1,154 @@
+/**
+ * Copyright (c) 2019, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+
+use bytes::{ BufMut, BytesMut };
+use std::io;
+use tokio::codec::{ Decoder, Encoder };
+
+/// Size of the big endian length prefixing each packet
+const HEADER_LEN: usize = 2;
+
+/**
+ * Split the stream in packets prefixed by their length (u16, big endian)
+ */
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RtpCodec;
+
+impl Decoder for RtpCodec {
+    type Item = Vec<u8>;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Vec<u8>>, io::Error> {
+        if src.len() < HEADER_LEN {
+            return Ok(None);
+        }
+        let pkt_len = ((src[0] as usize) << 8) + src[1] as usize;
+        if src.len() < HEADER_LEN + pkt_len {
+            // Wait for the rest of the packet
+            src.reserve(HEADER_LEN + pkt_len - src.len());
+            return Ok(None);
+        }
+        src.split_to(HEADER_LEN);
+        Ok(Some(src.split_to(pkt_len).to_vec()))
+    }
+}
+
+impl Encoder for RtpCodec {
+    type Item = Vec<u8>;
+    type Error = io::Error;
+
+    fn encode(&mut self, pkt: Vec<u8>, dst: &mut BytesMut) -> Result<(), io::Error> {
+        if pkt.len() > u16::MAX as usize {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "RTP packet too long"));
+        }
+        dst.reserve(HEADER_LEN + pkt.len());
+        dst.put_u16_be(pkt.len() as u16);
+        dst.put_slice(&pkt);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn encode_all(pkts: &[Vec<u8>]) -> Vec<u8> {
+        let mut buf = BytesMut::new();
+        for pkt in pkts {
+            RtpCodec.encode(pkt.clone(), &mut buf).unwrap();
+        }
+        buf.to_vec()
+    }
+
+    fn decode_chunks(stream: &[u8], cuts: &[usize]) -> Vec<Vec<u8>> {
+        let mut codec = RtpCodec;
+        let mut buf = BytesMut::new();
+        let mut pkts = Vec::new();
+        let mut start = 0;
+        for cut in cuts.iter().chain(std::iter::once(&stream.len())) {
+            let end = std::cmp::max(start, std::cmp::min(*cut, stream.len()));
+            buf.extend_from_slice(&stream[start..end]);
+            start = end;
+            while let Some(pkt) = codec.decode(&mut buf).unwrap() {
+                pkts.push(pkt);
+            }
+        }
+        assert!(buf.is_empty());
+        pkts
+    }
+
+    fn packets() -> impl Strategy<Value = Vec<Vec<u8>>> {
+        prop::collection::vec(prop::collection::vec(any::<u8>(), 0..600), 0..20)
+    }
+
+    proptest! {
+        #[test]
+        fn merged_frames_are_split(pkts in packets()) {
+            let stream = encode_all(&pkts);
+            prop_assert_eq!(decode_chunks(&stream, &[]), pkts);
+        }
+
+        #[test]
+        fn frames_split_at_any_boundary(pkts in packets(), cuts in prop::collection::vec(any::<prop::sample::Index>(), 0..40)) {
+            let stream = encode_all(&pkts);
+            let mut cuts: Vec<usize> = cuts.iter().map(|c| c.index(stream.len() + 1)).collect();
+            cuts.sort();
+            prop_assert_eq!(decode_chunks(&stream, &cuts), pkts);
+        }
+
+        #[test]
+        fn frames_fed_byte_per_byte(pkts in packets()) {
+            let stream = encode_all(&pkts);
+            let cuts: Vec<usize> = (0..stream.len()).collect();
+            prop_assert_eq!(decode_chunks(&stream, &cuts), pkts);
+        }
+    }
+
+    #[test]
+    fn incomplete_header_is_kept() {
+        let mut buf = BytesMut::from(&[0u8][..]);
+        assert_eq!(RtpCodec.decode(&mut buf).unwrap(), None);
+        assert_eq!(buf.len(), 1);
+    }
+
+    #[test]
+    fn max_len_packet_roundtrip() {
+        let pkt = vec![7u8; u16::MAX as usize];
+        let stream = encode_all(std::slice::from_ref(&pkt));
+        assert_eq!(&stream[..2], &[0xff, 0xff]);
+        assert_eq!(decode_chunks(&stream, &[1, 2, 3, 40000]), vec![pkt]);
+    }
+
+    #[test]
+    fn too_long_packet_is_rejected() {
+        let mut buf = BytesMut::new();
+        let pkt = vec![0u8; u16::MAX as usize + 1];
+        assert!(RtpCodec.encode(pkt, &mut buf).is_err());
+        assert!(buf.is_empty());
+    }
+}
@@ -0,0 +1,163 @@
+use crate::gen::item::{ Bonus, InteractiveItem, Malus };
+use crate::gen::utils::SquareType;
+use crate::net::msg::ServerMsg;
+use rmps::Serializer;
+use serde::Serialize;
+
+/**
+ * Messages written by the server, serialized like it does (for mock servers and tests)
+ */
+pub trait SerializedEvent {
+    /**
+     * @return the msgpack payload (without the length prefix)
+     */
+    fn to_vec(&self) -> Vec<u8>;
+}
+
+/**
+ * A player moved to x, y (in squares)
+ */
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct PlayerMove {
+    /// Id of the player
+    pub id: i32,
+    /// New column of the player
+    pub x: f32,
+    /// New row of the player
+    pub y: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// Last move of this player processed by the server (version 2)
+    pub seq: Option<u64>,
+}
+
+/**
+ * A bomb was kicked from old_x, old_y to x, y
+ */
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct BombMove {
+    /// Column the bomb was kicked from
+    pub old_x: f32,
+    /// Row the bomb was kicked from
+    pub old_y: f32,
+    /// New column of the bomb
+    pub x: f32,
+    /// New row of the bomb
+    pub y: f32
+}
+
+/**
+ * A player put a bomb on the square x, y
+ */
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct PlayerPutBomb {
+    /// Id of the player
+    pub id: i32,
+    /// Column of the bomb
+    pub x: usize,
+    /// Row of the bomb
+    pub y: usize
+}
+
+/**
+ * The bomb on the square w, h exploded
+ */
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct BombExplode {
+    /// Column of the bomb
+    pub w: u64,
+    /// Row of the bomb
+    pub h: u64,
+}
+
+/**
+ * A player died
+ */
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct PlayerDie {
+    /// Id of the player
+    pub id: u64,
+}
+
+/**
+ * Id of the player controlled by this client
+ */
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct PlayerIdentity {
+    /// Id of the player in the map
+    pub id: u64,
+}
+
+/**
+ * An item appeared on the square w, h
+ */
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct CreateItem {
+    /// The new item
+    pub item: Option<InteractiveItem>,
+    /// Column of the square
+    pub w: u64,
+    /// Row of the square
+    pub h: u64,
+}
+
+/**
+ * The type of the square x, y changed
+ */
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct UpdateSquare {
+    /// New type of the square
+    pub square: SquareType,
+    /// Column of the square
+    pub x: u64,
+    /// Row of the square
+    pub y: u64,
+}
+
+/**
+ * The item on the square w, h was destroyed
+ */
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct DestroyItem {
+    /// Column of the square
+    pub w: u64,
+    /// Row of the square
+    pub h: u64,
+}
+
+/**
+ * An effect active on a player
+ */
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct EffectUpdate {
+    /// Bonus causing the effect, if any
+    pub bonus: Option<Bonus>,
+    /// Malus causing the effect, if any
+    pub malus: Option<Malus>,
+    /// None if the effect lasts until the end of the game
+    pub remaining_ms: Option<u64>,
+}
+
+/**
+ * Stats and active effects of a player changed (capability player_update)
+ */
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct PlayerUpdate {
+    /// Id of the player
+    pub id: u64,
+    /// Squares reached by the flames of the bombs of the player
+    pub radius: u32,
+    /// Speed, 1000 is the default speed
+    pub speed_factor: u32,
+    /// Bombs the player can put at the same time
+    pub bomb: u32,
+    /// All the active effects, replacing the previous ones
+    pub effects: Vec<EffectUpdate>,
+}
+
+impl SerializedEvent for ServerMsg {
+    fn to_vec(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.serialize(&mut Serializer::new(&mut buf)).unwrap();
+        buf
+    }
+}
@@ -0,0 +1,224 @@
+/**
+ * Copyright (c) 2019, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::{ Duration, Instant };
+
+/**
+ * How often to ping the server and when to give up
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HeartbeatPolicy {
+    /// Time between two pings
+    pub interval: Duration,
+    /// Pongs missed in a row before the connection is lost. 0 never gives up
+    pub max_missed: u32,
+}
+
+impl Default for HeartbeatPolicy {
+    fn default() -> HeartbeatPolicy {
+        HeartbeatPolicy {
+            interval: Duration::from_secs(2),
+            max_missed: 3,
+        }
+    }
+}
+
+/// Pings kept waiting for their pong, the older ones are counted as missed
+const MAX_PENDING: usize = 64;
+
+/**
+ * Track pings sent to the server and estimate the round trip time from the pongs.
+ * The estimate is smoothed like the TCP retransmission timer (RFC 6298).
+ */
+#[derive(Debug, Default)]
+pub struct Heartbeat {
+    next_id: u64,
+    pending: VecDeque<(u64, Instant)>, // Pings without pong, oldest first
+    dropped: u32, // Pings without pong forgotten to keep at most MAX_PENDING
+    rtt: Option<Duration>,
+    jitter: Duration,
+}
+
+impl Heartbeat {
+    /**
+     * @return  a heartbeat without any ping sent
+     */
+    pub fn new() -> Heartbeat {
+        Heartbeat::default()
+    }
+
+    /**
+     * Forget everything, for a new connection
+     */
+    pub fn reset(&mut self) {
+        *self = Heartbeat::default();
+    }
+
+    /**
+     * Register a new ping
+     * @param now   when the ping is sent
+     * @return      the id to send
+     */
+    pub fn ping(&mut self, now: Instant) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        if self.pending.len() == MAX_PENDING {
+            self.pending.pop_front();
+            self.dropped = self.dropped.saturating_add(1);
+        }
+        self.pending.push_back((id, now));
+        id
+    }
+
+    /**
+     * Handle a pong from the server. Older pings are considered answered too.
+     * @param id    the id of the ping
+     * @param now   when the pong is received
+     * @return      the round trip time of this ping, None if it was not pending
+     */
+    pub fn pong(&mut self, id: u64, now: Instant) -> Option<Duration> {
+        let idx = self.pending.iter().position(|(pending_id, _)| *pending_id == id)?;
+        let (_, sent) = self.pending[idx];
+        self.pending.drain(..=idx);
+        self.dropped = 0;
+
+        let sample = now.duration_since(sent);
+        match self.rtt {
+            None => {
+                self.rtt = Some(sample);
+                self.jitter = sample / 2;
+            },
+            Some(rtt) => {
+                let diff = rtt.abs_diff(sample);
+                self.jitter = self.jitter * 3 / 4 + diff / 4;
+                self.rtt = Some(rtt * 7 / 8 + sample / 8);
+            },
+        }
+        Some(sample)
+    }
+
+    /**
+     * @return how many pings were not answered since the last pong
+     */
+    pub fn missed(&self) -> u32 {
+        self.dropped.saturating_add(self.pending.len() as u32)
+    }
+
+    /**
+     * @return the smoothed round trip time, None before the first pong
+     */
+    pub fn rtt(&self) -> Option<Duration> {
+        self.rtt
+    }
+
+    /**
+     * @return the mean deviation of the round trip time
+     */
+    pub fn jitter(&self) -> Duration {
+        self.jitter
+    }
+}
+
+impl fmt::Display for Heartbeat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.rtt() {
+            Some(rtt) => write!(f, "RTT {} ms ±{} ms", rtt.as_millis(), self.jitter().as_millis()),
+            None => write!(f, "RTT -"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_pong_sets_rtt() {
+        let start = Instant::now();
+        let mut heartbeat = Heartbeat::new();
+        let id = heartbeat.ping(start);
+        assert_eq!(heartbeat.missed(), 1);
+        assert_eq!(heartbeat.pong(id, start + Duration::from_millis(40)), Some(Duration::from_millis(40)));
+        assert_eq!(heartbeat.missed(), 0);
+        assert_eq!(heartbeat.rtt(), Some(Duration::from_millis(40)));
+        assert_eq!(heartbeat.jitter(), Duration::from_millis(20));
+        assert_eq!(heartbeat.to_string(), "RTT 40 ms ±20 ms");
+    }
+
+    #[test]
+    fn rtt_is_smoothed() {
+        let start = Instant::now();
+        let mut heartbeat = Heartbeat::new();
+        let id = heartbeat.ping(start);
+        heartbeat.pong(id, start + Duration::from_millis(80));
+        let id = heartbeat.ping(start);
+        heartbeat.pong(id, start + Duration::from_millis(160));
+        assert_eq!(heartbeat.rtt(), Some(Duration::from_millis(90)));
+        assert_eq!(heartbeat.jitter(), Duration::from_millis(50));
+    }
+
+    #[test]
+    fn pong_answers_older_pings() {
+        let start = Instant::now();
+        let mut heartbeat = Heartbeat::new();
+        heartbeat.ping(start);
+        heartbeat.ping(start);
+        let id = heartbeat.ping(start);
+        assert_eq!(heartbeat.missed(), 3);
+        assert!(heartbeat.pong(id, start).is_some());
+        assert_eq!(heartbeat.missed(), 0);
+        // Late pong for an answered ping
+        assert_eq!(heartbeat.pong(0, start), None);
+    }
+
+    #[test]
+    fn pending_pings_are_capped() {
+        let start = Instant::now();
+        let mut heartbeat = Heartbeat::new();
+        for _ in 0..MAX_PENDING + 10 {
+            heartbeat.ping(start);
+        }
+        assert_eq!(heartbeat.pending.len(), MAX_PENDING);
+        assert_eq!(heartbeat.missed(), MAX_PENDING as u32 + 10);
+        // The oldest pings are forgotten
+        assert_eq!(heartbeat.pong(0, start), None);
+        let id = heartbeat.ping(start);
+        assert!(heartbeat.pong(id, start).is_some());
+        assert_eq!(heartbeat.missed(), 0);
+    }
+
+    #[test]
+    fn reset_forgets_pending() {
+        let mut heartbeat = Heartbeat::new();
+        heartbeat.ping(Instant::now());
+        heartbeat.reset();
+        assert_eq!(heartbeat.missed(), 0);
+        assert_eq!(heartbeat.rtt(), None);
+    }
+}
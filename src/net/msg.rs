@@ -0,0 +1,362 @@
+/**
+ * Copyright (c) 2019, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+
+use rmps::Deserializer;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use crate::gen::utils::Direction;
+use crate::gen::map::Map;
+use super::diff_msg::*;
+
+// This file contains messages which will be wrapped via msgpack.
+// Each messages MUST have a unique msg_type.
+
+/// Version of the protocol spoken by this client. Increase it on breaking changes
+/// 2: moves have a sequence number, acknowledged in the player moves
+pub const PROTOCOL_VERSION: u32 = 2;
+/// Oldest server version this client can still talk to
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+/// Optional features supported by this client, announced in the hello
+pub const CAPABILITIES: [&str; 4] = ["ping", "input_sequence", "player_update", "game_over"];
+
+/**
+ * Header shared by every message
+ */
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub struct Msg {
+    /// Type of the message, like map or pong
+    pub msg_type: String,
+}
+
+/**
+ * Every command the client can send to the server
+ */
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "msg_type", rename_all = "snake_case")]
+pub enum ClientCommand {
+    /// First message of a connection: versions and capabilities of the client
+    Hello {
+        /// Newest version spoken by the client, PROTOCOL_VERSION
+        version: u32,
+        /// Oldest version spoken by the client, MIN_PROTOCOL_VERSION
+        min_version: u32,
+        /// Optional features supported by the client, CAPABILITIES
+        capabilities: Vec<String>,
+    },
+    /// Send player details such as the name
+    Player {
+        /// Name shown to the other players
+        name: String,
+    },
+    /// Create a new room
+    Create,
+    /// Join a room (0 is the lobby)
+    Join {
+        /// Id of the room
+        room: u64,
+    },
+    /// Leave the current room
+    Leave,
+    /// Start a new game in the current room
+    Launch,
+    /// Move the player. seq is only sent to servers speaking version 2 or newer
+    Move {
+        /// Where to go
+        direction: Direction,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        /// Number of the move, acknowledged in the player moves
+        seq: Option<u64>,
+    },
+    /// Put a bomb
+    Bomb,
+    /// Heartbeat, answered by a pong with the same id
+    Ping {
+        /// Id of the ping, increasing
+        id: u64,
+    },
+}
+
+/**
+ * Message containing the whole map of a game
+ */
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct MapMsg {
+    /// The map, with the players and items
+    pub map: Map,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    /// Names of the players, by id. Not sent by every server
+    pub names: Vec<String>,
+}
+
+/**
+ * Message when a player join a room or lobby (room 0)
+ */
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct JoinedMsg {
+    /// Id of the room
+    pub room: u64,
+    /// If the room was joined
+    pub success: bool
+}
+
+/**
+ * Answer to the hello: the version chosen by the server and its capabilities
+ */
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct WelcomeMsg {
+    /// Protocol version used for this connection
+    pub version: u32,
+    /// Optional features supported by both sides
+    pub capabilities: Vec<String>,
+}
+
+impl WelcomeMsg {
+    /**
+     * @return if this client can talk to the server
+     */
+    pub fn is_compatible(&self) -> bool {
+        self.version >= MIN_PROTOCOL_VERSION && self.version <= PROTOCOL_VERSION
+    }
+}
+
+/**
+ * End of the game (capability game_over). The players stay in the room
+ */
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct GameOverMsg {
+    #[serde(default)]
+    /// None for a draw, or to let the client pick the last survivor
+    pub winner: Option<u64>,
+}
+
+/**
+ * Answer to a ping
+ */
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct PongMsg {
+    /// Id of the ping
+    pub id: u64,
+}
+
+/**
+ * Every message the server can send to the client. The msg_type is used as the tag
+ * so the wire format stays the same as the old standalone structs.
+ */
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "msg_type")]
+pub enum ServerMsg {
+    /// A game starts
+    #[serde(rename = "map")]
+    Map(MapMsg),
+    /// Answer to a join
+    #[serde(rename = "joined")]
+    Joined(JoinedMsg),
+    /// A player moved
+    #[serde(rename = "player_move_diff")]
+    PlayerMove(PlayerMove),
+    /// A bomb was kicked
+    #[serde(rename = "bomb_move_diff")]
+    BombMove(BombMove),
+    /// A player put a bomb
+    #[serde(rename = "player_put_bomb_diff")]
+    PlayerPutBomb(PlayerPutBomb),
+    /// A bomb exploded
+    #[serde(rename = "bomb_explode")]
+    BombExplode(BombExplode),
+    /// A player died
+    #[serde(rename = "player_die")]
+    PlayerDie(PlayerDie),
+    /// Player controlled by this client
+    #[serde(rename = "player_identity")]
+    PlayerIdentity(PlayerIdentity),
+    /// An item appeared
+    #[serde(rename = "create_item")]
+    CreateItem(CreateItem),
+    /// A square changed
+    #[serde(rename = "update_square")]
+    UpdateSquare(UpdateSquare),
+    /// An item was destroyed
+    #[serde(rename = "destroy_item")]
+    DestroyItem(DestroyItem),
+    /// Stats and effects of a player changed (capability player_update)
+    #[serde(rename = "player_update_diff")]
+    PlayerUpdate(PlayerUpdate),
+    /// The game ended (capability game_over)
+    #[serde(rename = "game_over")]
+    GameOver(GameOverMsg),
+    /// Answer to a ping (capability ping)
+    #[serde(rename = "pong")]
+    Pong(PongMsg),
+    /// Answer to the hello
+    #[serde(rename = "welcome")]
+    Welcome(WelcomeMsg),
+}
+
+impl ServerMsg {
+    /// Every msg_type the client understands
+    pub const MSG_TYPES: [&'static str; 15] = [
+        "map", "joined", "player_move_diff", "bomb_move_diff", "player_put_bomb_diff",
+        "bomb_explode", "player_die", "player_identity", "create_item", "update_square",
+        "destroy_item", "player_update_diff", "game_over", "pong", "welcome",
+    ];
+
+    /**
+     * @return the msg_type used on the wire for this message
+     */
+    pub fn msg_type(&self) -> &'static str {
+        match self {
+            ServerMsg::Map(_) => "map",
+            ServerMsg::Joined(_) => "joined",
+            ServerMsg::PlayerMove(_) => "player_move_diff",
+            ServerMsg::BombMove(_) => "bomb_move_diff",
+            ServerMsg::PlayerPutBomb(_) => "player_put_bomb_diff",
+            ServerMsg::BombExplode(_) => "bomb_explode",
+            ServerMsg::PlayerDie(_) => "player_die",
+            ServerMsg::PlayerIdentity(_) => "player_identity",
+            ServerMsg::CreateItem(_) => "create_item",
+            ServerMsg::UpdateSquare(_) => "update_square",
+            ServerMsg::DestroyItem(_) => "destroy_item",
+            ServerMsg::PlayerUpdate(_) => "player_update_diff",
+            ServerMsg::GameOver(_) => "game_over",
+            ServerMsg::Pong(_) => "pong",
+            ServerMsg::Welcome(_) => "welcome",
+        }
+    }
+
+    /**
+     * Decode a packet received from the server
+     * @param pkt   the msgpack payload (without the length prefix)
+     * @return      the message or why it can't be decoded
+     */
+    pub fn decode(pkt: &[u8]) -> Result<ServerMsg, MsgError> {
+        // Read from the slice: lengths are checked against the packet before allocating
+        let mut de = Deserializer::from_read_ref(pkt);
+        match Deserialize::deserialize(&mut de) {
+            Ok(msg) => Ok(msg),
+            Err(e) => {
+                // Only look at the header when the message can't be decoded
+                let mut de = Deserializer::from_read_ref(pkt);
+                let header: Result<Msg, _> = Deserialize::deserialize(&mut de);
+                match header {
+                    Ok(header) if !ServerMsg::MSG_TYPES.contains(&&*header.msg_type) => {
+                        Err(MsgError::Unknown(header.msg_type))
+                    },
+                    _ => Err(MsgError::Malformed(e.to_string())),
+                }
+            }
+        }
+    }
+}
+
+/**
+ * Why a packet from the server was rejected
+ */
+#[derive(Debug, PartialEq)]
+pub enum MsgError {
+    /// The msg_type is not supported by this client
+    Unknown(String),
+    /// The packet is not a valid message
+    Malformed(String),
+    /// A game message (msg_type) received while there is no map
+    NoMap(&'static str),
+    /// A game message (msg_type) for a square outside of the map
+    OutOfMap {
+        /// Type of the message
+        msg_type: &'static str,
+        /// Column in the message
+        x: f64,
+        /// Row in the message
+        y: f64,
+    },
+    /// A game message (msg_type) for a player which is not in the map
+    UnknownPlayer {
+        /// Type of the message
+        msg_type: &'static str,
+        /// Id in the message, ids too big for an i64 wrap around
+        id: i64,
+    },
+    /// A message of an optional feature (capability) the server didn't announce
+    NotNegotiated(&'static str),
+}
+
+impl fmt::Display for MsgError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MsgError::Unknown(msg_type) => write!(f, "Unknown message type: {}", msg_type),
+            MsgError::Malformed(e) => write!(f, "Malformed message: {}", e),
+            MsgError::NoMap(msg_type) => write!(f, "{} received outside of a game", msg_type),
+            MsgError::OutOfMap { msg_type, x, y } => write!(f, "{} for ({}, {}) is outside of the map", msg_type, x, y),
+            MsgError::UnknownPlayer { msg_type, id } => write!(f, "{} for unknown player {}", msg_type, id),
+            MsgError::NotNegotiated(capability) => write!(f, "{} message from a server which didn't announce it", capability),
+        }
+    }
+}
+
+impl std::error::Error for MsgError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmps::Serializer;
+
+    fn encode<T: Serialize>(value: &T) -> Vec<u8> {
+        let mut buf = Vec::new();
+        value.serialize(&mut Serializer::new(&mut buf)).unwrap();
+        buf
+    }
+
+    #[test]
+    fn move_without_seq_keeps_version_1_format() {
+        #[derive(Serialize)]
+        struct MoveV1 {
+            msg_type: String,
+            direction: Direction,
+        }
+        let v1 = MoveV1 { msg_type: String::from("move"), direction: Direction::West };
+        assert_eq!(encode(&ClientCommand::Move { direction: Direction::West, seq: None }), encode(&v1));
+    }
+
+    #[test]
+    fn player_move_without_seq_is_decoded() {
+        #[derive(Serialize)]
+        struct PlayerMoveV1 {
+            msg_type: String,
+            id: i32,
+            x: f32,
+            y: f32,
+        }
+        let v1 = PlayerMoveV1 { msg_type: String::from("player_move_diff"), id: 1, x: 2.5, y: 3.5 };
+        let expected = ServerMsg::PlayerMove(PlayerMove { id: 1, x: 2.5, y: 3.5, seq: None });
+        assert_eq!(ServerMsg::decode(&encode(&v1)), Ok(expected));
+    }
+
+    #[test]
+    fn player_move_with_seq_roundtrip() {
+        let msg = ServerMsg::PlayerMove(PlayerMove { id: 1, x: 2.5, y: 3.5, seq: Some(7) });
+        assert_eq!(ServerMsg::decode(&encode(&msg)), Ok(msg));
+    }
+}
@@ -0,0 +1,116 @@
+/**
+ * Copyright (c) 2019, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+
+use ring::digest;
+use std::sync::{ Arc, Mutex };
+use tokio_rustls::rustls::{ Certificate, RootCertStore, ServerCertVerified, ServerCertVerifier, TLSError };
+
+/**
+ * SHA-256 fingerprint of a certificate
+ * @param cert_der  the DER encoded certificate
+ * @return          the digest as uppercase hex bytes separated by ':'
+ */
+pub fn fingerprint(cert_der: &[u8]) -> String {
+    let hash = digest::digest(&digest::SHA256, cert_der);
+    hash.as_ref().iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<String>>()
+        .join(":")
+}
+
+/**
+ * Trust-on-first-use verifier: only accept the server certificate whose fingerprint
+ * was pinned. The chain and the name are not verified, which allows self-signed
+ * certificates. The fingerprint presented by the server is kept so the user can be
+ * asked to pin it.
+ */
+pub struct PinnedCertVerifier {
+    pinned: Option<String>,
+    /// Fingerprint of the last certificate presented by the server
+    pub presented: Arc<Mutex<Option<String>>>,
+}
+
+impl PinnedCertVerifier {
+    /**
+     * @param pinned    the accepted fingerprint, None to reject every certificate
+     */
+    pub fn new(pinned: Option<String>) -> PinnedCertVerifier {
+        PinnedCertVerifier {
+            pinned,
+            presented: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+impl ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(&self,
+                          _roots: &RootCertStore,
+                          presented_certs: &[Certificate],
+                          _dns_name: webpki::DNSNameRef,
+                          _ocsp_response: &[u8]) -> Result<ServerCertVerified, TLSError> {
+        let cert = presented_certs.first().ok_or(TLSError::NoCertificatesPresented)?;
+        let presented = fingerprint(&cert.0);
+        *self.presented.lock().unwrap() = Some(presented.clone());
+        match self.pinned {
+            Some(ref pinned) if pinned.eq_ignore_ascii_case(&presented) => Ok(ServerCertVerified::assertion()),
+            _ => Err(TLSError::General(String::from("Server certificate is not pinned"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn verify(verifier: &PinnedCertVerifier, cert: &[u8]) -> Result<ServerCertVerified, TLSError> {
+        let name = webpki::DNSNameRef::try_from_ascii_str("localhost").unwrap();
+        verifier.verify_server_cert(&RootCertStore::empty(), &[Certificate(cert.to_vec())], name, &[])
+    }
+
+    #[test]
+    fn fingerprint_format() {
+        assert_eq!(
+            fingerprint(b""),
+            "E3:B0:C4:42:98:FC:1C:14:9A:FB:F4:C8:99:6F:B9:24:27:AE:41:E4:64:9B:93:4C:A4:95:99:1B:78:52:B8:55"
+        );
+    }
+
+    #[test]
+    fn unpinned_certificate_is_rejected_and_recorded() {
+        let verifier = PinnedCertVerifier::new(None);
+        assert!(verify(&verifier, b"cert").is_err());
+        assert_eq!(*verifier.presented.lock().unwrap(), Some(fingerprint(b"cert")));
+    }
+
+    #[test]
+    fn pinned_certificate_is_accepted() {
+        let verifier = PinnedCertVerifier::new(Some(fingerprint(b"cert").to_lowercase()));
+        assert!(verify(&verifier, b"cert").is_ok());
+        assert!(verify(&verifier, b"other cert").is_err());
+        assert_eq!(*verifier.presented.lock().unwrap(), Some(fingerprint(b"other cert")));
+    }
+}
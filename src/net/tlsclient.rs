@@ -0,0 +1,488 @@
+/**
+ * Copyright (c) 2019, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+
+use futures::Stream;
+use futures::sync::mpsc;
+use std::fmt;
+use std::fs;
+use std::io::{ self, BufReader };
+use std::sync::{ Arc, Mutex };
+use std::sync::atomic::{ AtomicBool, Ordering };
+use std::thread;
+use std::time::{ Duration, Instant };
+use tokio::codec::Framed;
+use tokio::net::TcpStream;
+use tokio::prelude::Future;
+use tokio::timer::{ Delay, Interval };
+use tokio_rustls::{ TlsConnector, rustls::ClientConfig, rustls::TLSError };
+use tokio_rustls::rustls::internal::pemfile;
+
+use crate::client::{ Client, Handshake, OUTBOUND_QUEUE_SIZE };
+use super::{ HeartbeatPolicy, PinnedCertVerifier, RtpCodec, ServerAddress };
+
+/// Time given to the server to answer the hello before assuming it predates the handshake
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/**
+ * State of the connection to the server, shown to the user
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConnectionState {
+    /// First connection in progress
+    Connecting,
+    /// Handshake done, the messages of the server are received
+    Connected,
+    /// Closed by the server or after a network error
+    Disconnected,
+    /// Waiting before this attempt to connect again, starting at 1
+    Reconnecting(u32),
+    /// The connection can't be established. Retrying won't help
+    Failed(String),
+    /// The server doesn't speak a protocol version supported by this client
+    Incompatible(String),
+    /// Fingerprint to confirm (trust on first use)
+    UntrustedCertificate(String),
+    /// The server presented another certificate than the pinned one
+    CertificateMismatch {
+        /// Pinned fingerprint
+        expected: String,
+        /// Fingerprint of the certificate of the server
+        presented: String,
+    },
+}
+
+impl ConnectionState {
+    /**
+     * @return if the connection is over and a new one can be started
+     */
+    pub fn is_closed(&self) -> bool {
+        !matches!(*self, ConnectionState::Connecting | ConnectionState::Connected | ConnectionState::Reconnecting(_))
+    }
+}
+
+impl fmt::Display for ConnectionState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConnectionState::Connecting => write!(f, "Connecting"),
+            ConnectionState::Connected => write!(f, "Connected"),
+            ConnectionState::Disconnected => write!(f, "Disconnected"),
+            ConnectionState::Reconnecting(attempt) => write!(f, "Reconnecting (attempt {})", attempt),
+            ConnectionState::Failed(ref reason) => write!(f, "{}", reason),
+            ConnectionState::Incompatible(ref reason) => write!(f, "Incompatible server: {}", reason),
+            ConnectionState::UntrustedCertificate(_) => write!(f, "Untrusted certificate"),
+            ConnectionState::CertificateMismatch { .. } => write!(f, "Certificate mismatch"),
+        }
+    }
+}
+
+/**
+ * When to try to connect again after the connection to the server is lost
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReconnectPolicy {
+    /// Attempts before giving up
+    pub max_attempts: u32,
+    /// Wait before the first attempt, doubled for each of the next ones
+    pub initial_delay: Duration,
+    /// Longest wait between two attempts
+    pub max_delay: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> ReconnectPolicy {
+        ReconnectPolicy {
+            max_attempts: 8,
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /**
+     * Exponential backoff
+     * @param attempt   the attempt to wait for, starting at 1
+     * @return          the time to wait before this attempt
+     */
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 1u32.checked_shl(attempt.saturating_sub(1)).unwrap_or(u32::MAX);
+        std::cmp::min(self.initial_delay.checked_mul(factor).unwrap_or(self.max_delay), self.max_delay)
+    }
+}
+
+/**
+ * Where and how to connect, and the client fed with the messages of the server
+ */
+pub struct TlsClientConfig {
+    /// Updated by the connection, read by the frontend
+    pub server_state: Arc<Mutex<Option<ConnectionState>>>,
+    /// Address of the server, host[:port]
+    pub addr: String,
+    /// Name used for SNI and to verify the certificate. Host of addr if empty, which
+    /// must then be a DNS name
+    pub server_name: String,
+    /// Path of the PEM CAs trusted instead of the web roots. Web roots if empty
+    pub cert: String,
+    /// PEM chain for client authentication. None if empty
+    pub client_cert: String,
+    /// PEM private key of client_cert
+    pub client_key: String,
+    /// Only trust the pinned fingerprint instead of CAs
+    pub trust_on_first_use: bool,
+    /// Pinned SHA-256 fingerprint. Nothing pinned if empty
+    pub fingerprint: String,
+    /// When to connect again after the connection is lost
+    pub reconnect: ReconnectPolicy,
+    /// How often to ping the server
+    pub heartbeat: HeartbeatPolicy,
+    /// Without a welcome after this time, the server is assumed to speak version 1
+    pub handshake_timeout: Duration,
+    /// Fed with the messages of the server
+    pub client: Arc<Mutex<Client>>
+}
+
+/**
+ * Runs the connection to the server. See TlsClient::start
+ */
+pub struct TlsClient {
+}
+
+/**
+ * Give a readable state for a failed connection
+ * @param e             the error from the connection
+ * @param server_name   the name the certificate was verified against
+ * @return              the state to show
+ */
+fn failed_state(e: &io::Error, server_name: &str) -> ConnectionState {
+    let tls_error = e.get_ref().and_then(|e| e.downcast_ref::<TLSError>());
+    match tls_error {
+        Some(TLSError::WebPKIError(err)) => ConnectionState::Failed(
+            format!("Certificate verification failed for {}: {:?}", server_name, err)
+        ),
+        Some(err) => ConnectionState::Failed(format!("TLS error: {}", err)),
+        None => ConnectionState::Disconnected,
+    }
+}
+
+/**
+ * How a connection attempt ended
+ */
+enum Attempt {
+    /// The session was established, then closed. server_state tells why
+    Ended,
+    /// The server can't be reached for now (name resolution or network error)
+    Unreachable(String),
+    /// Retrying won't help (configuration, server name, certificate), see server_state
+    Rejected,
+}
+
+impl TlsClient {
+    /**
+     * Read a PEM file
+     * @param path  the file to open
+     * @param what  what the file contains, for the error message
+     */
+    fn open_pem(path: &str, what: &str) -> Result<BufReader<fs::File>, String> {
+        fs::File::open(path)
+            .map(BufReader::new)
+            .map_err(|e| format!("Unable to open {} {}: {}", what, path, e))
+    }
+
+    /**
+     * Build the rustls configuration: trusted CAs and the optional client certificate
+     * @param client_config     the paths to load
+     * @param verifier          replaces the CAs verification if set
+     * @return                  the configuration or why it can't be loaded
+     */
+    fn tls_config(client_config: &TlsClientConfig, verifier: Option<Arc<PinnedCertVerifier>>)
+        -> Result<ClientConfig, String> {
+        let mut config = ClientConfig::new();
+        if let Some(verifier) = verifier {
+            config.dangerous().set_certificate_verifier(verifier);
+        } else if !client_config.cert.is_empty() {
+            let mut pem = TlsClient::open_pem(&client_config.cert, "certificate")?;
+            match config.root_store.add_pem_file(&mut pem) {
+                Ok((valid, _)) if valid > 0 => {},
+                _ => return Err(format!("No valid certificate in {}", client_config.cert)),
+            }
+        } else {
+            config.root_store.add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
+        }
+
+        if client_config.client_cert.is_empty() && client_config.client_key.is_empty() {
+            return Ok(config);
+        }
+        let mut pem = TlsClient::open_pem(&client_config.client_cert, "client certificate")?;
+        let cert_chain = match pemfile::certs(&mut pem) {
+            Ok(certs) if !certs.is_empty() => certs,
+            _ => return Err(format!("No valid certificate in {}", client_config.client_cert)),
+        };
+        // Accept PKCS8 and RSA keys
+        let mut pem = TlsClient::open_pem(&client_config.client_key, "client key")?;
+        let mut keys = pemfile::pkcs8_private_keys(&mut pem).unwrap_or_default();
+        if keys.is_empty() {
+            let mut pem = TlsClient::open_pem(&client_config.client_key, "client key")?;
+            keys = pemfile::rsa_private_keys(&mut pem).unwrap_or_default();
+        }
+        if keys.is_empty() {
+            return Err(format!("No valid private key in {}", client_config.client_key));
+        }
+        config.set_single_client_cert(cert_chain, keys.remove(0));
+        Ok(config)
+    }
+
+    /**
+     * Connect to the server and exchange packets until the connection is closed.
+     * If the connection is lost, connect again following client_config.reconnect and
+     * resume the session (player and room).
+     * @param client_config     where to connect and the client to feed
+     * @param outbound          packets to write to the server, in order
+     */
+    pub fn start(client_config: &TlsClientConfig, outbound: mpsc::Receiver<Vec<u8>>) {
+        let mut outbound = outbound;
+        let mut had_session = false;
+        let mut attempt = 0;
+        loop {
+            match TlsClient::connect(client_config, outbound) {
+                Attempt::Ended => {
+                    had_session = true;
+                    attempt = 0;
+                    // Closed by the server or after a network error, not for an incompatible version
+                    if *client_config.server_state.lock().unwrap() != Some(ConnectionState::Disconnected) {
+                        return;
+                    }
+                },
+                // Only retry transient errors for a session which was working
+                Attempt::Unreachable(reason) if !had_session => {
+                    error!("{}", reason);
+                    *client_config.server_state.lock().unwrap() = Some(ConnectionState::Failed(reason));
+                    return;
+                },
+                Attempt::Unreachable(reason) => warn!("{}", reason),
+                Attempt::Rejected => return,
+            }
+            attempt += 1;
+            if attempt > client_config.reconnect.max_attempts {
+                warn!("Giving up reconnecting after {} attempts", attempt - 1);
+                *client_config.server_state.lock().unwrap() = Some(ConnectionState::Disconnected);
+                return;
+            }
+            *client_config.server_state.lock().unwrap() = Some(ConnectionState::Reconnecting(attempt));
+            let delay = client_config.reconnect.delay(attempt);
+            info!("Connection lost, reconnecting in {:?} (attempt {})", delay, attempt);
+            thread::sleep(delay);
+
+            // The previous queue was consumed by the old connection
+            let (tx, rx) = mpsc::channel::<Vec<u8>>(OUTBOUND_QUEUE_SIZE);
+            client_config.client.lock().unwrap().tx = tx;
+            outbound = rx;
+        }
+    }
+
+    /**
+     * Open one connection and exchange packets until it is closed
+     * @param client_config     where to connect and the client to feed
+     * @param outbound          packets to write to the server, in order
+     * @return                  how the attempt ended
+     */
+    fn connect(client_config: &TlsClientConfig, outbound: mpsc::Receiver<Vec<u8>>) -> Attempt {
+        let client = client_config.client.clone();
+        let client_heartbeat = client_config.client.clone();
+        let client_handshake = client_config.client.clone();
+        let heartbeat = client_config.heartbeat;
+        let handshake_timeout = client_config.handshake_timeout;
+        let established = Arc::new(AtomicBool::new(false));
+        let established_cln = established.clone();
+        let unreachable = Arc::new(Mutex::new(None));
+        let unreachable_cln = unreachable.clone();
+
+        let server_state = client_config.server_state.clone();
+        let server_state_err = client_config.server_state.clone();
+
+        let failed = |reason: String| {
+            error!("{}", reason);
+            *client_config.server_state.lock().unwrap() = Some(ConnectionState::Failed(reason));
+            Attempt::Rejected
+        };
+        let addr = match ServerAddress::parse(&client_config.addr) {
+            Ok(addr) => addr,
+            Err(e) => return failed(e.to_string()),
+        };
+        let verifier = if client_config.trust_on_first_use {
+            let pinned = Some(client_config.fingerprint.clone()).filter(|f| !f.is_empty());
+            Some(Arc::new(PinnedCertVerifier::new(pinned)))
+        } else {
+            None
+        };
+        let presented = verifier.as_ref().map(|v| v.presented.clone());
+        let pinned = client_config.fingerprint.clone();
+        let config = match TlsClient::tls_config(client_config, verifier) {
+            Ok(config) => TlsConnector::from(Arc::new(config)),
+            Err(e) => return failed(e),
+        };
+        let server = match addr.resolve() {
+            Ok(server) => server,
+            Err(e) => return Attempt::Unreachable(e.to_string()),
+        };
+
+        let server_name = match addr.server_name(&client_config.server_name) {
+            Ok(server_name) => server_name,
+            Err(e) => return failed(e.to_string()),
+        };
+        let domain = match webpki::DNSNameRef::try_from_ascii_str(&server_name) {
+            Ok(domain) => domain.to_owned(),
+            Err(_) => return failed(
+                format!("Invalid server name: {}. Please set the name in the certificate", server_name)
+            ),
+        };
+
+        let socket = TcpStream::connect(&server);
+        let done = socket
+        .and_then(move |stream| {
+            config.connect(domain.as_ref(), stream)
+        })
+        .and_then(move |socket| {
+            established_cln.store(true, Ordering::SeqCst);
+            client.lock().unwrap().resume_session();
+            let client_end = client.clone();
+            let reader_state = server_state.clone();
+            let handshake_state = server_state.clone();
+            let (sink, stream) = Framed::new(socket, RtpCodec).split();
+            // Only woken up when a packet is received
+            let reader = stream.for_each(move |pkt| {
+                let mut client = client.lock().unwrap();
+                // Invalid packets are logged and counted by the client
+                let _ = client.parse_rtp(pkt);
+                match client.handshake {
+                    Handshake::Incompatible(ref reason) => {
+                        return Err(io::Error::new(io::ErrorKind::InvalidData, reason.clone()));
+                    },
+                    Handshake::Done { .. } => {
+                        // Usable once the server accepted our version
+                        let mut state = reader_state.lock().unwrap();
+                        if *state != Some(ConnectionState::Connected) {
+                            *state = Some(ConnectionState::Connected);
+                        }
+                    },
+                    Handshake::Pending => {},
+                }
+                Ok(())
+            });
+            // Only woken up when a packet is queued or the socket is writable again
+            let writer = outbound
+                .map_err(|_| io::Error::other("Outbound queue closed"))
+                .forward(sink);
+            // Ping the server and give up when it stops answering
+            let heartbeat = Interval::new_interval(heartbeat.interval)
+                .map_err(io::Error::other)
+                .for_each(move |_| {
+                    let mut client = client_heartbeat.lock().unwrap();
+                    // Old servers don't answer pings, the connection is lost when the socket is closed
+                    if !client.supports("ping") {
+                        return Ok(());
+                    }
+                    if heartbeat.max_missed > 0 && client.heartbeat.missed() >= heartbeat.max_missed {
+                        warn!("No pong for {} pings, the server is gone", client.heartbeat.missed());
+                        return Err(io::Error::new(io::ErrorKind::TimedOut, "Heartbeat timeout"));
+                    }
+                    if let Err(e) = client.ping() {
+                        warn!("Can't send ping: {}", e);
+                    }
+                    Ok(())
+                });
+            // Servers older than the handshake never send the welcome
+            let handshake = Delay::new(Instant::now() + handshake_timeout)
+                .map_err(io::Error::other)
+                .map(move |_| {
+                    let mut client = client_handshake.lock().unwrap();
+                    client.handshake_timeout();
+                    if let Handshake::Done { .. } = client.handshake {
+                        let mut state = handshake_state.lock().unwrap();
+                        if *state != Some(ConnectionState::Connected) {
+                            *state = Some(ConnectionState::Connected);
+                        }
+                    }
+                });
+            // The connection is over as soon as one side stops
+            let session = reader.select2(writer).then(|_| Ok::<(), io::Error>(()));
+            session.select2(heartbeat.join(handshake)).then(move |_| {
+                let state = match client_end.lock().unwrap().handshake {
+                    Handshake::Incompatible(ref reason) => ConnectionState::Incompatible(reason.clone()),
+                    // Probably an old server which doesn't know the hello
+                    Handshake::Pending => ConnectionState::Incompatible(
+                        String::from("the server closed the connection without accepting the protocol version")
+                    ),
+                    Handshake::Done { .. } => ConnectionState::Disconnected,
+                };
+                *server_state.lock().unwrap() = Some(state);
+                Ok(())
+            })
+        }).map_err(move |e| {
+            let presented = presented.and_then(|p| p.lock().unwrap().clone());
+            let state = match presented {
+                Some(presented) if pinned.is_empty() => ConnectionState::UntrustedCertificate(presented),
+                Some(presented) if !pinned.eq_ignore_ascii_case(&presented) => {
+                    warn!("Certificate mismatch, expected {} got {}", pinned, presented);
+                    ConnectionState::CertificateMismatch { expected: pinned, presented }
+                },
+                _ => failed_state(&e, &server_name),
+            };
+            match state {
+                // Not a TLS error: the server can't be reached
+                ConnectionState::Disconnected => {
+                    *unreachable_cln.lock().unwrap() = Some(format!("Can't connect to {}: {}", server, e));
+                    return;
+                },
+                ConnectionState::Failed(ref reason) => error!("{}", reason),
+                _ => {},
+            }
+            *server_state_err.lock().unwrap() = Some(state);
+        });
+
+        tokio::run(done);
+        if established.load(Ordering::SeqCst) {
+            return Attempt::Ended;
+        }
+        let unreachable = unreachable.lock().unwrap().take();
+        unreachable.map_or(Attempt::Rejected, Attempt::Unreachable)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reconnect_delay_is_exponential_and_capped() {
+        let policy = ReconnectPolicy::default();
+        assert_eq!(policy.delay(1), Duration::from_millis(500));
+        assert_eq!(policy.delay(2), Duration::from_secs(1));
+        assert_eq!(policy.delay(4), Duration::from_secs(4));
+        assert_eq!(policy.delay(7), Duration::from_secs(30));
+        assert_eq!(policy.delay(100), Duration::from_secs(30));
+    }
+}
@@ -0,0 +1,255 @@
+/**
+ * Copyright (c) 2019, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+
+use crate::gen::item::Walkable;
+use crate::gen::map::Map;
+use crate::gen::utils::{ Direction, MapPlayer };
+use std::collections::VecDeque;
+
+/// Distance of one move at the default speed (speed_factor of 1000), like the server
+pub const MOVE_STEP: f32 = 0.1;
+
+/// Moves kept waiting for the server, the older ones are forgotten
+const MAX_PENDING: usize = 64;
+
+/**
+ * Where a move leads a player, using the same rules as the server
+ * @param map       the current map
+ * @param player    the player to move
+ * @param direction where to go
+ * @param step      the distance at the default speed
+ * @return          the new position, None if the move is blocked
+ */
+pub fn predict_move(map: &Map, player: &MapPlayer, direction: Direction, step: f32) -> Option<(f32, f32)> {
+    let distance = step * player.speed_factor as f32 / 1000.0;
+    let (x, y) = match direction {
+        Direction::North => (player.x, player.y - distance),
+        Direction::South => (player.x, player.y + distance),
+        Direction::West => (player.x - distance, player.y),
+        Direction::East => (player.x + distance, player.y),
+    };
+    if x < 0.0 || y < 0.0 {
+        return None;
+    }
+    let pos = (x as usize, y as usize);
+    let idx = map.index(pos.0, pos.1)?;
+    if pos != (player.x as usize, player.y as usize) {
+        // Entering a new square
+        if !map.squares[idx].sq_type.walkable(player, &pos) {
+            return None;
+        }
+        if let Some(ref item) = map.items[idx] {
+            if !item.walkable(player, &pos) {
+                return None;
+            }
+        }
+    }
+    Some((x, y))
+}
+
+/**
+ * Move the local player as soon as a key is pressed instead of waiting for the server.
+ * Each move gets a sequence number; when the server sends the position after a given
+ * move, the moves it didn't process yet are replayed on top of it.
+ */
+#[derive(Debug)]
+pub struct Prediction {
+    /// Distance of a move at the default speed
+    pub step: f32,
+    next_seq: u64,
+    pending: VecDeque<(u64, Direction)>, // Moves not acknowledged by the server, oldest first
+}
+
+impl Prediction {
+    /**
+     * @param step  the distance of a move at the default speed
+     */
+    pub fn new(step: f32) -> Prediction {
+        Prediction {
+            step,
+            next_seq: 1,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /**
+     * Forget the pending moves, for a new game or connection
+     */
+    pub fn reset(&mut self) {
+        self.pending.clear();
+    }
+
+    /**
+     * Apply a move locally. A blocked move is still sent but not replayed
+     * @param map       the map to update
+     * @param id        the local player
+     * @param direction where to go
+     * @return          the sequence number to send with the move
+     */
+    pub fn input(&mut self, map: &mut Map, id: usize, direction: Direction) -> u64 {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        if self.apply(map, id, direction) {
+            if self.pending.len() == MAX_PENDING {
+                self.pending.pop_front();
+            }
+            self.pending.push_back((seq, direction));
+        }
+        seq
+    }
+
+    /**
+     * Handle the authoritative position of the local player
+     * @param map   the map to update
+     * @param id    the local player
+     * @param x     position sent by the server
+     * @param y     position sent by the server
+     * @param ack   the last move processed by the server
+     */
+    pub fn reconcile(&mut self, map: &mut Map, id: usize, x: f32, y: f32, ack: u64) {
+        while self.pending.front().is_some_and(|(seq, _)| *seq <= ack) {
+            self.pending.pop_front();
+        }
+        if let Some(player) = map.players.get_mut(id) {
+            player.x = x;
+            player.y = y;
+        }
+        for (_, direction) in self.pending.clone() {
+            self.apply(map, id, direction);
+        }
+    }
+
+    /**
+     * @return the number of moves not acknowledged by the server
+     */
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /**
+     * @return if the player moved
+     */
+    fn apply(&self, map: &mut Map, id: usize, direction: Direction) -> bool {
+        let player = match map.players.get(id) {
+            Some(player) => *player,
+            None => return false,
+        };
+        match predict_move(map, &player, direction, self.step) {
+            Some((x, y)) => {
+                map.players[id].x = x;
+                map.players[id].y = y;
+                true
+            },
+            None => false,
+        }
+    }
+}
+
+impl Default for Prediction {
+    fn default() -> Prediction {
+        Prediction::new(MOVE_STEP)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gen::utils::SquareType;
+
+    // Only empty squares, with the player in the square (1, 1)
+    fn map() -> Map {
+        let mut map = Map::new(11, 11);
+        for square in &mut map.squares {
+            square.sq_type = SquareType::Empty;
+        }
+        for item in &mut map.items {
+            *item = None;
+        }
+        map.players[0].x = 1.5;
+        map.players[0].y = 1.5;
+        map.players[0].speed_factor = 1000;
+        map
+    }
+
+    #[test]
+    fn move_is_applied_locally() {
+        let mut map = map();
+        let mut prediction = Prediction::new(0.25);
+        assert_eq!(prediction.input(&mut map, 0, Direction::East), 1);
+        assert_eq!(prediction.input(&mut map, 0, Direction::South), 2);
+        assert_eq!((map.players[0].x, map.players[0].y), (1.75, 1.75));
+        assert_eq!(prediction.pending(), 2);
+    }
+
+    #[test]
+    fn blocked_square_stops_the_move() {
+        let mut map = map();
+        let idx = map.index(2, 1).unwrap();
+        map.squares[idx].sq_type = SquareType::Block;
+        let mut prediction = Prediction::new(0.5);
+        prediction.input(&mut map, 0, Direction::East);
+        assert_eq!((map.players[0].x, map.players[0].y), (1.5, 1.5));
+        // Nothing to replay
+        assert_eq!(prediction.pending(), 0);
+    }
+
+    #[test]
+    fn pending_moves_are_capped() {
+        let mut map = map();
+        let mut prediction = Prediction::new(0.0);
+        for _ in 0..MAX_PENDING + 10 {
+            prediction.input(&mut map, 0, Direction::East);
+        }
+        assert_eq!(prediction.pending(), MAX_PENDING);
+        // The oldest moves were forgotten
+        assert_eq!(prediction.pending.front().map(|(seq, _)| *seq), Some(11));
+    }
+
+    #[test]
+    fn outside_of_map_is_blocked() {
+        let map = map();
+        let mut player = map.players[0];
+        player.x = 0.1;
+        assert_eq!(predict_move(&map, &player, Direction::West, 0.25), None);
+    }
+
+    #[test]
+    fn reconcile_replays_pending_moves() {
+        let mut map = map();
+        let mut prediction = Prediction::new(0.25);
+        prediction.input(&mut map, 0, Direction::East);
+        prediction.input(&mut map, 0, Direction::East);
+        prediction.input(&mut map, 0, Direction::South);
+        // The server only processed the first move, and was slower
+        prediction.reconcile(&mut map, 0, 1.625, 1.5, 1);
+        assert_eq!(prediction.pending(), 2);
+        assert_eq!((map.players[0].x, map.players[0].y), (1.875, 1.75));
+        prediction.reconcile(&mut map, 0, 2.0, 2.0, 3);
+        assert_eq!(prediction.pending(), 0);
+        assert_eq!((map.players[0].x, map.players[0].y), (2.0, 2.0));
+    }
+}
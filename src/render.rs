@@ -0,0 +1,739 @@
+/**
+ * Copyright (c) 2019, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+
+use crate::bombs::{ Flame, LiveBomb };
+use crate::gen::item::{ BombItem, Bonus, DestructibleBox, Item, Malus };
+use crate::gen::map::Map;
+use crate::gen::utils::{ Direction, SquareType };
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::time::{ Duration, Instant };
+use tui::buffer::Buffer;
+use tui::layout::Rect;
+use tui::style::{ Color, Modifier, Style };
+use tui::widgets::Widget;
+use unicode_width::{ UnicodeWidthChar, UnicodeWidthStr };
+
+/// Squares of the danger zone burning sooner than this are highlighted
+pub const DANGER_SOON: Duration = Duration::from_secs(1);
+/// Names of the built-in glyph sets
+pub const THEMES: [&str; 3] = ["emoji", "ascii", "16colors"];
+/// Variants of Bonus which can have their own glyph, Custom ones are named Custom:name
+pub(crate) const BONUSES: [&str; 5] = ["ImproveBombRadius", "PunchBombs", "ImproveSpeed", "RepelBombs", "MoreBombs"];
+/// Variants of Malus which can have their own glyph, Custom ones are named Custom:name
+pub(crate) const MALUSES: [&str; 5] = ["Slow", "UltraFast", "SpeedBomb", "DropBombs", "InvertedControls"];
+
+/**
+ * How to draw something on the map
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Glyph {
+    /// Characters drawn, can be empty
+    pub symbol: String,
+    /// Colors of the characters. The background is applied on the whole square
+    pub style: Style,
+}
+
+impl Glyph {
+    /**
+     * @param symbol    characters drawn
+     * @param style     colors of the characters and background of the square
+     */
+    pub fn new(symbol: &str, style: Style) -> Glyph {
+        Glyph { symbol: String::from(symbol), style }
+    }
+}
+
+/**
+ * Kind of the items drawn on the map
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ItemKind {
+    /// A DestructibleBox
+    DestructibleBox,
+    /// A BombItem
+    Bomb,
+    /// Any Bonus
+    Bonus,
+    /// Any Malus
+    Malus,
+}
+
+impl ItemKind {
+    /**
+     * @param item  an item of the map
+     * @return      its kind, None for items unknown to the client
+     */
+    pub fn of(item: &dyn Item) -> Option<ItemKind> {
+        let item = item.as_any();
+        if item.is::<DestructibleBox>() {
+            Some(ItemKind::DestructibleBox)
+        } else if item.is::<BombItem>() {
+            Some(ItemKind::Bomb)
+        } else if item.is::<Bonus>() {
+            Some(ItemKind::Bonus)
+        } else if item.is::<Malus>() {
+            Some(ItemKind::Malus)
+        } else {
+            None
+        }
+    }
+}
+
+/**
+ * Glyph of everything drawn on the map.
+ * Squares are filled with their symbol repeated, shifted by one character
+ * on each row. Walls are drawn on their side of the square, the symbol gives the
+ * first, middle and last characters. Items, players and flames are drawn once
+ * at the top left of the square.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Glyphs {
+    /// Empty squares
+    pub empty: Glyph,
+    /// Water squares
+    pub water: Glyph,
+    /// Block squares
+    pub block: Glyph,
+    /// Squares with a wall on their north side
+    pub wall_north: Glyph,
+    /// Squares with a wall on their south side
+    pub wall_south: Glyph,
+    /// Squares with a wall on their west side
+    pub wall_west: Glyph,
+    /// Squares with a wall on their east side
+    pub wall_east: Glyph,
+    /// Boxes destroyed by the bombs
+    pub destructible_box: Glyph,
+    /// Bombs waiting to explode
+    pub bomb: Glyph,
+    /// Bonuses without a glyph in bonuses
+    pub bonus: Glyph,
+    /// Maluses without a glyph in maluses
+    pub malus: Glyph,
+    /// By variant, bonus is used for the others
+    pub bonuses: HashMap<String, Glyph>,
+    /// By variant, malus is used for the others
+    pub maluses: HashMap<String, Glyph>,
+    /// By player id, used again when there are more players
+    pub players: Vec<Glyph>,
+    /// Squares burning after an explosion
+    pub flame: Glyph,
+    /// Seconds left before the explosion of the bombs
+    pub countdown: Style,
+    /// Background of the danger zone
+    pub danger: Color,
+    /// Background of the danger zone burning soon
+    pub danger_soon: Color,
+}
+
+impl Default for Glyphs {
+    /**
+     * @return  emojis, for squares of 2 columns or more
+     */
+    fn default() -> Glyphs {
+        let ground = Style::default().bg(Color::Yellow);
+        Glyphs {
+            empty: Glyph::new(" ", ground),
+            water: Glyph::new("~.", Style::default().bg(Color::Blue)),
+            block: Glyph::new(" ", Style::default().bg(Color::Black)),
+            wall_north: Glyph::new("╔═╗", ground),
+            wall_south: Glyph::new("╚═╝", ground),
+            wall_west: Glyph::new("╔║╚", ground),
+            wall_east: Glyph::new("╗║╝", ground),
+            destructible_box: Glyph::new(" ", Style::default().bg(Color::Rgb(55, 27, 0))),
+            bomb: Glyph::new("💣", Style::default()),
+            bonus: Glyph::new("🌟", Style::default()),
+            malus: Glyph::new("💀", Style::default()),
+            bonuses: HashMap::new(),
+            maluses: HashMap::new(),
+            players: ["🐧", "🐥", "🦂", "🐙"].iter().map(|p| Glyph::new(p, Style::default())).collect(),
+            flame: Glyph::new("🔥", Style::default().bg(Color::Red)),
+            countdown: Style::default().fg(Color::Red).modifier(Modifier::BOLD),
+            danger: Color::Rgb(255, 160, 60),
+            danger_soon: Color::LightRed,
+        }
+    }
+}
+
+impl Glyphs {
+    /**
+     * @return  ASCII characters, for any terminal. Only the danger zone is colored
+     */
+    pub fn ascii() -> Glyphs {
+        let plain = Style::default();
+        let bold = plain.modifier(Modifier::BOLD);
+        Glyphs {
+            empty: Glyph::new(" ", plain),
+            water: Glyph::new("~", plain),
+            block: Glyph::new("#", plain),
+            wall_north: Glyph::new("+-+", plain),
+            wall_south: Glyph::new("+-+", plain),
+            wall_west: Glyph::new("+|+", plain),
+            wall_east: Glyph::new("+|+", plain),
+            destructible_box: Glyph::new("%", plain),
+            bomb: Glyph::new("o", bold),
+            bonus: Glyph::new("+", plain),
+            malus: Glyph::new("-", plain),
+            bonuses: HashMap::new(),
+            maluses: HashMap::new(),
+            players: ["1", "2", "3", "4"].iter().map(|p| Glyph::new(p, bold)).collect(),
+            flame: Glyph::new("*", bold),
+            countdown: bold,
+            danger: Color::Yellow,
+            danger_soon: Color::Red,
+        }
+    }
+
+    /**
+     * @return  one column characters with the 16 colors of the terminal.
+     *          Also used when the squares are too small for the other glyphs
+     */
+    pub fn colors16() -> Glyphs {
+        let ground = Style::default().bg(Color::LightYellow);
+        let player = Style::default().fg(Color::Black).modifier(Modifier::BOLD);
+        Glyphs {
+            empty: Glyph::new(" ", ground),
+            water: Glyph::new("~", Style::default().fg(Color::White).bg(Color::Blue)),
+            block: Glyph::new(" ", Style::default().bg(Color::Black)),
+            wall_north: Glyph::new("╔═╗", ground.fg(Color::Black)),
+            wall_south: Glyph::new("╚═╝", ground.fg(Color::Black)),
+            wall_west: Glyph::new("╔║╚", ground.fg(Color::Black)),
+            wall_east: Glyph::new("╗║╝", ground.fg(Color::Black)),
+            destructible_box: Glyph::new(" ", Style::default().bg(Color::Yellow)),
+            bomb: Glyph::new("o", Style::default().fg(Color::Black)),
+            bonus: Glyph::new("+", Style::default().fg(Color::Green)),
+            malus: Glyph::new("-", Style::default().fg(Color::Magenta)),
+            bonuses: HashMap::new(),
+            maluses: HashMap::new(),
+            players: ["1", "2", "3", "4"].iter().map(|p| Glyph::new(p, player)).collect(),
+            flame: Glyph::new("*", Style::default().fg(Color::Yellow).bg(Color::Red)),
+            countdown: Style::default().fg(Color::Red).modifier(Modifier::BOLD),
+            danger: Color::LightMagenta,
+            danger_soon: Color::LightRed,
+        }
+    }
+
+    /**
+     * @param name  one of THEMES
+     * @return      the built-in glyph set, None if unknown
+     */
+    pub fn builtin(name: &str) -> Option<Glyphs> {
+        match name {
+            "emoji" => Some(Glyphs::default()),
+            "ascii" => Some(Glyphs::ascii()),
+            "16colors" => Some(Glyphs::colors16()),
+            _ => None,
+        }
+    }
+
+    /**
+     * @param cell_w    columns of a square
+     * @return          if the glyphs drawn once in a square fit in it
+     */
+    pub fn fits(&self, cell_w: u16) -> bool {
+        let once = [&self.bomb, &self.bonus, &self.malus, &self.flame];
+        once.iter().cloned()
+            .chain(self.bonuses.values())
+            .chain(self.maluses.values())
+            .chain(self.players.iter())
+            .flat_map(|glyph| glyph.symbol.split('\n'))
+            .all(|line| line.width() <= cell_w as usize)
+    }
+
+    /**
+     * @param sq_type   type of a square
+     * @return          how to fill the square
+     */
+    pub fn square(&self, sq_type: SquareType) -> &Glyph {
+        match sq_type {
+            SquareType::Empty => &self.empty,
+            SquareType::Water => &self.water,
+            SquareType::Block => &self.block,
+            SquareType::Wall(Direction::North) => &self.wall_north,
+            SquareType::Wall(Direction::South) => &self.wall_south,
+            SquareType::Wall(Direction::West) => &self.wall_west,
+            SquareType::Wall(Direction::East) => &self.wall_east,
+        }
+    }
+
+    /**
+     * @param kind  kind of an item
+     * @return      how to draw the item
+     */
+    pub fn item(&self, kind: ItemKind) -> &Glyph {
+        match kind {
+            ItemKind::DestructibleBox => &self.destructible_box,
+            ItemKind::Bomb => &self.bomb,
+            ItemKind::Bonus => &self.bonus,
+            ItemKind::Malus => &self.malus,
+        }
+    }
+
+    /**
+     * @param bonus a bonus of the map
+     * @return      how to draw it
+     */
+    pub fn bonus(&self, bonus: &Bonus) -> &Glyph {
+        let variant: Cow<str> = match bonus {
+            Bonus::ImproveBombRadius => BONUSES[0].into(),
+            Bonus::PunchBombs => BONUSES[1].into(),
+            Bonus::ImproveSpeed => BONUSES[2].into(),
+            Bonus::RepelBombs => BONUSES[3].into(),
+            Bonus::MoreBombs => BONUSES[4].into(),
+            Bonus::Custom(name) => format!("Custom:{}", name).into(),
+        };
+        self.bonuses.get(&*variant).unwrap_or(&self.bonus)
+    }
+
+    /**
+     * @param malus a malus of the map
+     * @return      how to draw it
+     */
+    pub fn malus(&self, malus: &Malus) -> &Glyph {
+        let variant: Cow<str> = match malus {
+            Malus::Slow => MALUSES[0].into(),
+            Malus::UltraFast => MALUSES[1].into(),
+            Malus::SpeedBomb => MALUSES[2].into(),
+            Malus::DropBombs => MALUSES[3].into(),
+            Malus::InvertedControls => MALUSES[4].into(),
+            Malus::Custom(name) => format!("Custom:{}", name).into(),
+        };
+        self.maluses.get(&*variant).unwrap_or(&self.malus)
+    }
+
+    /**
+     * @param id    the player
+     * @return      how to draw the player
+     */
+    pub fn player(&self, id: usize) -> Option<&Glyph> {
+        if self.players.is_empty() {
+            return None;
+        }
+        self.players.get(id % self.players.len())
+    }
+}
+
+/**
+ * Characters of a glyph filling a square, prepared once per frame
+ */
+struct Texture {
+    chars: Vec<char>,
+    narrow: bool, // If all the characters take one column, so they can be put in the cells directly
+    style: Style,
+}
+
+impl Texture {
+    fn new(glyph: &Glyph) -> Texture {
+        let chars: Vec<char> = if glyph.symbol.is_empty() { vec![' '] } else { glyph.symbol.chars().collect() };
+        let narrow = chars.iter().all(|c| c.width() == Some(1));
+        Texture { chars, narrow, style: glyph.style }
+    }
+}
+
+/**
+ * Draw the map, with its items, players, bombs and flames, directly in the
+ * buffer of the terminal
+ */
+pub struct MapWidget<'a> {
+    map: &'a Map,
+    glyphs: &'a Glyphs,
+    cell_w: u16, // Columns of a square
+    cell_h: u16, // Rows of a square
+    positions: &'a [(f32, f32)], // Position of the players, by id. The map is used if empty
+    bombs: &'a [LiveBomb],
+    flames: &'a [Flame],
+    danger: &'a [Option<Instant>], // When each square will burn, can be empty
+    now: Instant,
+}
+
+impl<'a> MapWidget<'a> {
+    /**
+     * @param map       the game to draw
+     * @param glyphs    how to draw it
+     */
+    pub fn new(map: &'a Map, glyphs: &'a Glyphs) -> MapWidget<'a> {
+        MapWidget {
+            map,
+            glyphs,
+            cell_w: 1,
+            cell_h: 1,
+            positions: &[],
+            bombs: &[],
+            flames: &[],
+            danger: &[],
+            now: Instant::now(),
+        }
+    }
+
+    /**
+     * @param w     columns of a square
+     * @param h     rows of a square
+     */
+    pub fn cell_size(mut self, w: u16, h: u16) -> MapWidget<'a> {
+        self.cell_w = w;
+        self.cell_h = h;
+        self
+    }
+
+    /**
+     * @param positions where to draw the players, by id
+     */
+    pub fn positions(mut self, positions: &'a [(f32, f32)]) -> MapWidget<'a> {
+        self.positions = positions;
+        self
+    }
+
+    /**
+     * @param bombs     bombs waiting to explode, to show their countdown
+     */
+    pub fn bombs(mut self, bombs: &'a [LiveBomb]) -> MapWidget<'a> {
+        self.bombs = bombs;
+        self
+    }
+
+    /**
+     * @param flames    squares burning
+     */
+    pub fn flames(mut self, flames: &'a [Flame]) -> MapWidget<'a> {
+        self.flames = flames;
+        self
+    }
+
+    /**
+     * @param danger    when each square will burn, see danger_zone
+     */
+    pub fn danger(mut self, danger: &'a [Option<Instant>]) -> MapWidget<'a> {
+        self.danger = danger;
+        self
+    }
+
+    /**
+     * @param now   current time, for the countdowns
+     */
+    pub fn now(mut self, now: Instant) -> MapWidget<'a> {
+        self.now = now;
+        self
+    }
+
+    /**
+     * @param area  where the map is drawn
+     * @param x     column of the square
+     * @param y     row of the square
+     * @return      the part of the square inside the area
+     */
+    fn cell(&self, area: Rect, x: f32, y: f32) -> Option<Rect> {
+        let x = (x.max(0.0) * self.cell_w as f32) as u32;
+        let y = (y.max(0.0) * self.cell_h as f32) as u32;
+        if x >= area.width as u32 || y >= area.height as u32 {
+            return None;
+        }
+        let (x, y) = (x as u16, y as u16);
+        Some(Rect::new(
+            area.x + x,
+            area.y + y,
+            self.cell_w.min(area.width - x),
+            self.cell_h.min(area.height - y),
+        ))
+    }
+
+    /**
+     * Fill a square with a symbol repeated
+     */
+    fn fill(buf: &mut Buffer, rect: Rect, texture: &Texture) {
+        let chars = &texture.chars;
+        for row in 0..rect.height {
+            if texture.narrow {
+                for col in 0..rect.width {
+                    let c = chars[(row + col) as usize % chars.len()];
+                    buf.get_mut(rect.x + col, rect.y + row).set_char(c).set_style(texture.style);
+                }
+            } else {
+                let line: String = chars.iter().cycle().skip(row as usize % chars.len()).take(rect.width as usize).collect();
+                for col in 0..rect.width {
+                    buf.get_mut(rect.x + col, rect.y + row).reset();
+                }
+                buf.set_stringn(rect.x, rect.y + row, line, rect.width as usize, texture.style);
+            }
+        }
+    }
+
+    /**
+     * Draw a wall on one side of a square
+     */
+    fn wall(buf: &mut Buffer, rect: Rect, glyph: &Glyph, d: Direction) {
+        let mut chars = glyph.symbol.chars();
+        let (first, middle, last) = match (chars.next(), chars.next(), chars.next()) {
+            (None, _, _) => return,
+            (Some(first), Some(middle), Some(last)) => (first, middle, last),
+            (Some(c), _, _) => (c, c, c),
+        };
+        let vertical = d == Direction::West || d == Direction::East;
+        let len = if vertical { rect.height } else { rect.width };
+        // Corners are only drawn if the square is large enough for them
+        let corners = rect.width > 1 && rect.height > 1;
+        for i in 0..len {
+            let c = match i {
+                0 if corners => first,
+                i if corners && i == len - 1 => last,
+                _ => middle,
+            };
+            let (x, y) = match d {
+                Direction::North => (rect.x + i, rect.y),
+                Direction::South => (rect.x + i, rect.y + rect.height - 1),
+                Direction::West => (rect.x, rect.y + i),
+                Direction::East => (rect.x + rect.width - 1, rect.y + i),
+            };
+            buf.get_mut(x, y).set_char(c).set_style(glyph.style);
+        }
+    }
+
+    /**
+     * Draw a glyph once at the top left of a square, over what is already drawn
+     */
+    fn put(&self, buf: &mut Buffer, rect: Rect, symbol: &str, style: Style) {
+        if style.bg != Color::Reset {
+            self.background(rect, buf, style.bg);
+        }
+        let mut rows = symbol.split('\n');
+        for row in 0..rect.height {
+            match rows.next() {
+                Some(line) => buf.set_stringn(rect.x, rect.y + row, line, rect.width as usize, style.bg(buf.get(rect.x, rect.y + row).style.bg)),
+                None => break,
+            }
+        }
+    }
+}
+
+impl<'a> Widget for MapWidget<'a> {
+    fn draw(&mut self, area: Rect, buf: &mut Buffer) {
+        if self.cell_w == 0 || self.cell_h == 0 || !area.intersects(*buf.area()) {
+            return;
+        }
+        let area = area.intersection(*buf.area());
+        let glyphs = self.glyphs;
+        let empty = Texture::new(&glyphs.empty);
+        let water = Texture::new(&glyphs.water);
+        let block = Texture::new(&glyphs.block);
+        let destructible_box = Texture::new(&glyphs.destructible_box);
+
+        for y in 0..self.map.h {
+            for x in 0..self.map.w {
+                let rect = match self.cell(area, x as f32, y as f32) {
+                    Some(rect) => rect,
+                    None => continue,
+                };
+                let pos = x + self.map.w * y;
+                let sq_type = self.map.squares[pos].sq_type;
+                if let SquareType::Wall(d) = sq_type {
+                    MapWidget::fill(buf, rect, &empty);
+                    MapWidget::wall(buf, rect, glyphs.square(sq_type), d);
+                    continue;
+                }
+                let texture = match sq_type {
+                    SquareType::Water => &water,
+                    SquareType::Block => &block,
+                    _ => &empty,
+                };
+                MapWidget::fill(buf, rect, texture);
+                if sq_type != SquareType::Empty {
+                    continue;
+                }
+                let item = match self.map.items.get(pos).and_then(|i| i.as_ref()) {
+                    Some(item) => item.as_ref(),
+                    None => continue,
+                };
+                let kind = match ItemKind::of(item) {
+                    Some(kind) => kind,
+                    None => continue,
+                };
+                let glyph = match kind {
+                    ItemKind::Bonus => item.as_any().downcast_ref().map_or(&glyphs.bonus, |bonus| glyphs.bonus(bonus)),
+                    ItemKind::Malus => item.as_any().downcast_ref().map_or(&glyphs.malus, |malus| glyphs.malus(malus)),
+                    kind => glyphs.item(kind),
+                };
+                if kind == ItemKind::DestructibleBox {
+                    MapWidget::fill(buf, rect, &destructible_box);
+                    continue;
+                }
+                self.put(buf, rect, &glyph.symbol, glyph.style);
+                if kind == ItemKind::Bomb {
+                    // Estimated seconds before the explosion: under the bomb, next to it
+                    // or instead of it when there is no room left
+                    if let Some(bomb) = self.bombs.iter().find(|b| b.x == x && b.y == y) {
+                        let countdown = bomb.remaining(self.now).as_millis().div_ceil(1000).to_string();
+                        let bomb_width = glyph.symbol.lines().next().map_or(0, |line| line.width()) as u16;
+                        if rect.height > 1 {
+                            self.put(buf, Rect { y: rect.y + 1, height: 1, ..rect }, &countdown, glyphs.countdown);
+                        } else if bomb_width < rect.width {
+                            let free = Rect { x: rect.x + bomb_width, width: rect.width - bomb_width, ..rect };
+                            self.put(buf, free, &countdown, glyphs.countdown);
+                        } else {
+                            self.put(buf, rect, &countdown, glyphs.countdown);
+                        }
+                    }
+                }
+            }
+        }
+
+        for (id, player) in self.map.players.iter().enumerate() {
+            if player.dead {
+                continue;
+            }
+            let (x, y) = self.positions.get(id).cloned().unwrap_or((player.x, player.y));
+            if let (Some(rect), Some(glyph)) = (self.cell(area, x, y), glyphs.player(id)) {
+                self.put(buf, rect, &glyph.symbol, glyph.style);
+            }
+        }
+
+        for (idx, burn) in self.danger.iter().enumerate() {
+            if let Some(burn) = burn {
+                let (x, y) = (idx % self.map.w, idx / self.map.w);
+                if let Some(rect) = self.cell(area, x as f32, y as f32) {
+                    // Brighter when the square is about to burn
+                    let color = if burn.saturating_duration_since(self.now) < DANGER_SOON { glyphs.danger_soon } else { glyphs.danger };
+                    self.background(rect, buf, color);
+                }
+            }
+        }
+
+        for flame in self.flames {
+            if let Some(rect) = self.cell(area, flame.x as f32, flame.y as f32) {
+                self.put(buf, rect, &glyphs.flame.symbol, glyphs.flame.style);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bombs::FUSE_TIME;
+    use crate::gen::utils::{ MapPlayer, Square };
+
+    fn small_map() -> Map {
+        let player = MapPlayer { x: 0.0, y: 0.0, radius: 1, speed_factor: 1000, bomb: 1, dead: false };
+        Map {
+            w: 3,
+            h: 2,
+            squares: vec![Square { sq_type: SquareType::Empty }; 3 * 2],
+            players: vec![player],
+            items: (0..3 * 2).map(|_| None).collect(),
+        }
+    }
+
+    fn symbols(buf: &Buffer, y: u16) -> String {
+        (0..buf.area().width).map(|x| buf.get(x, y).symbol.clone()).collect()
+    }
+
+    #[test]
+    fn items_are_drawn_from_the_glyphs() {
+        let mut map = small_map();
+        map.squares[1].sq_type = SquareType::Wall(Direction::North);
+        map.squares[3].sq_type = SquareType::Water;
+        map.items[2] = Some(Box::new(BombItem {}));
+        map.items[5] = Some(Box::new(Bonus::MoreBombs));
+        let glyphs = Glyphs::default();
+        let now = Instant::now();
+        let bombs = [LiveBomb { x: 2, y: 0, owner: 0, radius: 1, placed: now - FUSE_TIME / 2 }];
+        let mut buf = Buffer::empty(Rect::new(0, 0, 9, 6));
+        MapWidget::new(&map, &glyphs).cell_size(3, 3).bombs(&bombs).now(now).draw(Rect::new(0, 0, 9, 6), &mut buf);
+
+        // Emojis take 2 columns, the second one is left blank
+        assert_eq!(symbols(&buf, 0), "🐧  ╔═╗💣  ");
+        assert_eq!(symbols(&buf, 1), "      2  ");
+        assert_eq!(symbols(&buf, 3), "~.~   🌟  ");
+        assert_eq!(symbols(&buf, 4), ".~.      ");
+        assert_eq!(buf.get(0, 5).style.bg, Color::Blue);
+        assert_eq!(buf.get(4, 5).style.bg, Color::Yellow);
+        assert_eq!(buf.get(6, 1).style, glyphs.countdown.bg(Color::Yellow));
+    }
+
+    #[test]
+    fn compact_squares_show_the_countdown_and_flames() {
+        let mut map = small_map();
+        map.items[2] = Some(Box::new(BombItem {}));
+        map.squares[5].sq_type = SquareType::Wall(Direction::West);
+        let glyphs = Glyphs::colors16();
+        let now = Instant::now();
+        let bombs = [LiveBomb { x: 2, y: 0, owner: 0, radius: 1, placed: now }];
+        let flames = [Flame { x: 1, y: 0, until: now + FUSE_TIME }];
+        let mut buf = Buffer::empty(Rect::new(0, 0, 3, 2));
+        MapWidget::new(&map, &glyphs).bombs(&bombs).flames(&flames).now(now).draw(Rect::new(0, 0, 3, 2), &mut buf);
+
+        assert_eq!(symbols(&buf, 0), "1*3");
+        assert_eq!(symbols(&buf, 1), "  ║");
+        assert_eq!(buf.get(1, 0).style.bg, Color::Red);
+    }
+
+    #[test]
+    fn countdown_is_shown_in_one_row_squares() {
+        let mut map = small_map();
+        map.items[1] = Some(Box::new(BombItem {}));
+        map.items[2] = Some(Box::new(BombItem {}));
+        let now = Instant::now();
+        let bombs = [
+            LiveBomb { x: 1, y: 0, owner: 0, radius: 1, placed: now },
+            LiveBomb { x: 2, y: 0, owner: 0, radius: 1, placed: now - FUSE_TIME / 2 },
+        ];
+        let area = Rect::new(0, 0, 6, 2);
+        // The emoji takes the whole square
+        let mut buf = Buffer::empty(area);
+        MapWidget::new(&map, &Glyphs::default()).cell_size(2, 1).bombs(&bombs).now(now).draw(area, &mut buf);
+        assert_eq!(symbols(&buf, 0), "🐧 3 2 ");
+        assert_eq!(buf.get(2, 0).style, Glyphs::default().countdown.bg(Color::Yellow));
+        // Next to a one column bomb
+        let mut buf = Buffer::empty(area);
+        MapWidget::new(&map, &Glyphs::ascii()).cell_size(2, 1).bombs(&bombs).now(now).draw(area, &mut buf);
+        assert_eq!(symbols(&buf, 0), "1 o3o2");
+    }
+
+    #[test]
+    fn moving_players_are_cut_to_the_map() {
+        let map = small_map();
+        let glyphs = Glyphs::colors16();
+        let mut buf = Buffer::empty(Rect::new(0, 0, 10, 10));
+        let area = Rect::new(1, 1, 3, 2);
+        MapWidget::new(&map, &glyphs).positions(&[(2.5, 1.0)]).draw(area, &mut buf);
+        assert_eq!(buf.get(3, 2).symbol, "1");
+
+        let mut buf = Buffer::empty(Rect::new(0, 0, 10, 10));
+        MapWidget::new(&map, &glyphs).positions(&[(3.0, 1.0)]).draw(area, &mut buf);
+        assert!((0..10).all(|y| !symbols(&buf, y).contains('1')));
+        // Nothing is drawn outside of the map
+        assert_eq!(symbols(&buf, 0), " ".repeat(10));
+        assert_eq!(buf.get(4, 1).style.bg, Color::Reset);
+    }
+
+    #[test]
+    fn items_and_players_are_looked_up() {
+        assert_eq!(ItemKind::of(&BombItem {}), Some(ItemKind::Bomb));
+        assert_eq!(ItemKind::of(&Malus::Slow), Some(ItemKind::Malus));
+        assert_eq!(Glyphs::default().player(5), Glyphs::default().player(1));
+    }
+}
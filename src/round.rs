@@ -0,0 +1,140 @@
+/**
+ * Copyright (c) 2019, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+
+use std::time::{ Duration, Instant };
+
+/**
+ * How a game ended
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoundResult {
+    /// None for a draw
+    pub winner: Option<u64>,
+    /// Players from the winner to the first dead
+    pub ranking: Vec<u64>,
+    /// Time from the start of the game to its end
+    pub duration: Duration,
+}
+
+/**
+ * Progress of the current game: who died and when, then the result
+ */
+#[derive(Clone, Debug)]
+pub struct Round {
+    /// When the map of the game was received
+    pub started: Instant,
+    /// Dead players, first dead first
+    pub deaths: Vec<(u64, Instant)>,
+    /// Set when the server ends the game
+    pub result: Option<RoundResult>,
+}
+
+impl Round {
+    /**
+     * @param started   when the map was received
+     */
+    pub fn new(started: Instant) -> Round {
+        Round {
+            started,
+            deaths: Vec::new(),
+            result: None,
+        }
+    }
+
+    /**
+     * @param id    the player
+     * @param now   time of the death
+     */
+    pub fn player_died(&mut self, id: u64, now: Instant) {
+        if self.died_at(id).is_none() {
+            self.deaths.push((id, now));
+        }
+    }
+
+    /**
+     * @param id    the player
+     * @return      when the player died, None if alive
+     */
+    pub fn died_at(&self, id: u64) -> Option<Instant> {
+        self.deaths.iter().find(|(dead, _)| *dead == id).map(|(_, time)| *time)
+    }
+
+    /**
+     * End the game
+     * @param players   number of players in the game
+     * @param winner    winner announced by the server. If None, the last survivor
+     * @param now       time of the end
+     */
+    pub fn finish(&mut self, players: u64, winner: Option<u64>, now: Instant) {
+        let survivors: Vec<u64> = (0..players).filter(|id| self.died_at(*id).is_none()).collect();
+        let winner = winner.or(if survivors.len() == 1 { Some(survivors[0]) } else { None });
+        let mut ranking: Vec<u64> = winner.into_iter().collect();
+        ranking.extend(survivors.iter().filter(|id| Some(**id) != winner));
+        ranking.extend(self.deaths.iter().rev().map(|(id, _)| *id).filter(|id| Some(*id) != winner));
+        self.result = Some(RoundResult {
+            winner,
+            ranking,
+            duration: now.duration_since(self.started),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn last_survivor_wins() {
+        let start = Instant::now();
+        let mut round = Round::new(start);
+        round.player_died(2, start + Duration::from_secs(10));
+        round.player_died(0, start + Duration::from_secs(20));
+        round.player_died(0, start + Duration::from_secs(25));
+        round.player_died(3, start + Duration::from_secs(30));
+        round.finish(4, None, start + Duration::from_secs(31));
+        let result = round.result.unwrap();
+        assert_eq!(result.winner, Some(1));
+        assert_eq!(result.ranking, vec![1, 3, 0, 2]);
+        assert_eq!(result.duration, Duration::from_secs(31));
+    }
+
+    #[test]
+    fn draw_and_winner_from_server() {
+        let start = Instant::now();
+        let mut round = Round::new(start);
+        round.player_died(0, start);
+        round.player_died(1, start);
+        round.finish(2, None, start);
+        assert_eq!(round.result.as_ref().unwrap().winner, None);
+        assert_eq!(round.result.as_ref().unwrap().ranking, vec![1, 0]);
+
+        // Time out: the server decides between the survivors
+        let mut round = Round::new(start);
+        round.finish(3, Some(2), start);
+        assert_eq!(round.result.unwrap().ranking, vec![2, 0, 1]);
+    }
+}
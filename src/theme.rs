@@ -0,0 +1,446 @@
+/**
+ * Copyright (c) 2019, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+
+use crate::render::{ Glyph, Glyphs, BONUSES, MALUSES };
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use tui::style::{ Color, Modifier, Style };
+
+/**
+ * Why a theme can't be used
+ */
+#[derive(Debug, PartialEq)]
+pub enum ThemeError {
+    /// The file can't be read
+    Io(String),
+    /// The file is not a valid theme
+    Json(String),
+    /// The base is not one of THEMES
+    UnknownBase(String),
+    /// A color can't be parsed
+    Color(String),
+    /// A modifier can't be parsed
+    Modifier(String),
+    /// A key of bonuses or maluses is not a variant name nor Custom:name
+    UnknownItem(String),
+    /// The compact section has its own compact section
+    NestedCompact,
+}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ThemeError::Io(e) => write!(f, "Can't read the theme: {}", e),
+            ThemeError::Json(e) => write!(f, "Invalid theme: {}", e),
+            ThemeError::UnknownBase(name) => write!(f, "Unknown base theme: {}", name),
+            ThemeError::Color(color) => write!(f, "Unknown color: {}", color),
+            ThemeError::Modifier(modifier) => write!(f, "Unknown modifier: {}", modifier),
+            ThemeError::UnknownItem(variant) => write!(f, "Unknown bonus or malus: {}", variant),
+            ThemeError::NestedCompact => write!(f, "The compact glyphs can't have a compact section"),
+        }
+    }
+}
+
+impl std::error::Error for ThemeError {}
+
+/**
+ * A glyph in a theme file. Missing values are taken from the base theme
+ */
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GlyphDef {
+    symbol: Option<String>,
+    fg: Option<String>, // Color name (red, lightblue...), #rrggbb or 0 to 255
+    bg: Option<String>,
+    modifiers: Option<Vec<String>>, // bold, italic, reversed...
+}
+
+/**
+ * A theme file, in JSON. Every field is optional
+ */
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeDef {
+    base: Option<String>, // Built-in theme completed by this one, emoji by default
+    empty: Option<GlyphDef>,
+    water: Option<GlyphDef>,
+    block: Option<GlyphDef>,
+    wall_north: Option<GlyphDef>,
+    wall_south: Option<GlyphDef>,
+    wall_west: Option<GlyphDef>,
+    wall_east: Option<GlyphDef>,
+    destructible_box: Option<GlyphDef>,
+    bomb: Option<GlyphDef>,
+    bonus: Option<GlyphDef>,
+    malus: Option<GlyphDef>,
+    #[serde(default)]
+    bonuses: HashMap<String, GlyphDef>, // By variant name, like ImproveSpeed, or Custom:name
+    #[serde(default)]
+    maluses: HashMap<String, GlyphDef>, // By variant name, like Slow, or Custom:name
+    players: Option<Vec<GlyphDef>>,
+    flame: Option<GlyphDef>,
+    countdown: Option<GlyphDef>, // Only the colors are used
+    danger: Option<String>,
+    danger_soon: Option<String>,
+    compact: Option<Box<ThemeDef>>, // Glyphs for squares too small for this theme
+}
+
+/**
+ * Glyphs of a theme, with the ones used when the squares are too small for them
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    /// Glyphs for the squares they fit in
+    pub glyphs: Glyphs,
+    /// Glyphs for smaller squares
+    pub compact: Glyphs,
+}
+
+impl Default for Theme {
+    /**
+     * @return  the emoji theme
+     */
+    fn default() -> Theme {
+        Theme::builtin("emoji").unwrap()
+    }
+}
+
+impl Theme {
+    /**
+     * @param name  one of THEMES
+     * @return      the built-in theme, None if unknown
+     */
+    pub fn builtin(name: &str) -> Option<Theme> {
+        Some(Theme {
+            glyphs: Glyphs::builtin(name)?,
+            compact: Glyphs::builtin(compact_base(name))?,
+        })
+    }
+
+    /**
+     * @param cell_w    columns of a square
+     * @return          the glyphs to draw squares of this width
+     */
+    pub fn glyphs(&self, cell_w: u16) -> &Glyphs {
+        if self.glyphs.fits(cell_w) {
+            &self.glyphs
+        } else {
+            &self.compact
+        }
+    }
+}
+
+/**
+ * @param base  one of THEMES
+ * @return      the built-in theme used for the squares too small for base
+ */
+fn compact_base(base: &str) -> &'static str {
+    match base {
+        "ascii" => "ascii",
+        _ => "16colors",
+    }
+}
+
+/**
+ * @param name  a color name (red, lightblue...), #rrggbb or an index from 0 to 255
+ * @return      the color
+ */
+pub fn parse_color(name: &str) -> Result<Color, ThemeError> {
+    let color = match &*name.to_lowercase().replace(['_', '-', ' '], "") {
+        "reset" | "default" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        hex if hex.starts_with('#') && hex.len() == 7 => {
+            let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2).unwrap_or_default(), 16)
+                .map_err(|_| ThemeError::Color(String::from(name)));
+            Color::Rgb(channel(1)?, channel(3)?, channel(5)?)
+        },
+        index => Color::Indexed(index.parse().map_err(|_| ThemeError::Color(String::from(name)))?),
+    };
+    Ok(color)
+}
+
+/**
+ * @param name  bold, dim, italic, underlined, slow_blink, rapid_blink, reversed, hidden or crossed_out
+ * @return      the modifier
+ */
+fn parse_modifier(name: &str) -> Result<Modifier, ThemeError> {
+    let modifier = match &*name.to_lowercase() {
+        "bold" => Modifier::BOLD,
+        "dim" => Modifier::DIM,
+        "italic" => Modifier::ITALIC,
+        "underlined" => Modifier::UNDERLINED,
+        "slow_blink" => Modifier::SLOW_BLINK,
+        "rapid_blink" => Modifier::RAPID_BLINK,
+        "reversed" => Modifier::REVERSED,
+        "hidden" => Modifier::HIDDEN,
+        "crossed_out" => Modifier::CROSSED_OUT,
+        _ => return Err(ThemeError::Modifier(String::from(name))),
+    };
+    Ok(modifier)
+}
+
+impl GlyphDef {
+    /**
+     * @param base  the glyph completed by this one
+     * @return      the glyph
+     */
+    fn glyph(&self, base: &Glyph) -> Result<Glyph, ThemeError> {
+        Ok(Glyph {
+            symbol: self.symbol.clone().unwrap_or_else(|| base.symbol.clone()),
+            style: self.style(base.style)?,
+        })
+    }
+
+    /**
+     * @param base  the style completed by this one
+     * @return      the style
+     */
+    fn style(&self, base: Style) -> Result<Style, ThemeError> {
+        let mut style = base;
+        if let Some(fg) = &self.fg {
+            style.fg = parse_color(fg)?;
+        }
+        if let Some(bg) = &self.bg {
+            style.bg = parse_color(bg)?;
+        }
+        if let Some(modifiers) = &self.modifiers {
+            style.modifier = Modifier::empty();
+            for modifier in modifiers {
+                style.modifier |= parse_modifier(modifier)?;
+            }
+        }
+        Ok(style)
+    }
+}
+
+/**
+ * @param def   glyphs of a theme file
+ * @param base  the built-in theme used if def has no base
+ * @return      the glyphs, completing their base theme
+ */
+fn parse_glyphs(def: &ThemeDef, base: &str) -> Result<Glyphs, ThemeError> {
+    let base = def.base.as_ref().map_or(base, |base| base.as_str());
+    let mut glyphs = Glyphs::builtin(base).ok_or_else(|| ThemeError::UnknownBase(String::from(base)))?;
+
+    let fields = [
+        (&def.empty, &mut glyphs.empty),
+        (&def.water, &mut glyphs.water),
+        (&def.block, &mut glyphs.block),
+        (&def.wall_north, &mut glyphs.wall_north),
+        (&def.wall_south, &mut glyphs.wall_south),
+        (&def.wall_west, &mut glyphs.wall_west),
+        (&def.wall_east, &mut glyphs.wall_east),
+        (&def.destructible_box, &mut glyphs.destructible_box),
+        (&def.bomb, &mut glyphs.bomb),
+        (&def.bonus, &mut glyphs.bonus),
+        (&def.malus, &mut glyphs.malus),
+        (&def.flame, &mut glyphs.flame),
+    ];
+    for (def, glyph) in fields {
+        if let Some(def) = def {
+            *glyph = def.glyph(glyph)?;
+        }
+    }
+    for (variant, def) in &def.bonuses {
+        if !is_item_key(variant, &BONUSES) {
+            return Err(ThemeError::UnknownItem(variant.clone()));
+        }
+        let glyph = def.glyph(&glyphs.bonus)?;
+        glyphs.bonuses.insert(variant.clone(), glyph);
+    }
+    for (variant, def) in &def.maluses {
+        if !is_item_key(variant, &MALUSES) {
+            return Err(ThemeError::UnknownItem(variant.clone()));
+        }
+        let glyph = def.glyph(&glyphs.malus)?;
+        glyphs.maluses.insert(variant.clone(), glyph);
+    }
+    if let Some(players) = &def.players {
+        // Slots not defined keep the glyph of the base theme
+        let base = glyphs.players.clone();
+        glyphs.players = players.iter().enumerate()
+            .map(|(id, def)| def.glyph(&base[id % base.len()]))
+            .collect::<Result<_, _>>()?;
+        if glyphs.players.len() < base.len() {
+            glyphs.players.extend_from_slice(&base[glyphs.players.len()..]);
+        }
+    }
+    if let Some(countdown) = &def.countdown {
+        glyphs.countdown = countdown.style(glyphs.countdown)?;
+    }
+    if let Some(danger) = &def.danger {
+        glyphs.danger = parse_color(danger)?;
+    }
+    if let Some(danger_soon) = &def.danger_soon {
+        glyphs.danger_soon = parse_color(danger_soon)?;
+    }
+    Ok(glyphs)
+}
+
+/**
+ * @param key       a key of the bonuses or maluses of a theme file
+ * @param variants  the variants of the item
+ * @return          if the key is one of variants or Custom:name
+ */
+fn is_item_key(key: &str, variants: &[&str]) -> bool {
+    variants.contains(&key) || key.strip_prefix("Custom:").is_some_and(|name| !name.is_empty())
+}
+
+/**
+ * @param json  a theme file
+ * @return      the theme, completing its base theme
+ */
+pub fn parse_theme(json: &str) -> Result<Theme, ThemeError> {
+    let def: ThemeDef = serde_json::from_str(json).map_err(|e| ThemeError::Json(e.to_string()))?;
+    let base = def.base.as_ref().map_or("emoji", |base| base.as_str());
+    let glyphs = parse_glyphs(&def, base)?;
+    // Without a compact section, the compact glyphs of the base theme are used
+    let compact = match &def.compact {
+        Some(compact) if compact.compact.is_some() => return Err(ThemeError::NestedCompact),
+        Some(compact) => parse_glyphs(compact, compact_base(base))?,
+        None => Glyphs::builtin(compact_base(base)).unwrap(),
+    };
+    Ok(Theme { glyphs, compact })
+}
+
+/**
+ * @param theme a built-in theme (see THEMES) or the path of a theme file
+ * @return      the theme
+ */
+pub fn load_theme(theme: &str) -> Result<Theme, ThemeError> {
+    if let Some(theme) = Theme::builtin(theme) {
+        return Ok(theme);
+    }
+    let json = fs::read_to_string(Path::new(theme)).map_err(|e| ThemeError::Io(e.to_string()))?;
+    parse_theme(&json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gen::item::{ Bonus, Malus };
+    use crate::render::THEMES;
+
+    #[test]
+    fn colors_are_parsed() {
+        assert_eq!(parse_color("LightRed"), Ok(Color::LightRed));
+        assert_eq!(parse_color("dark_gray"), Ok(Color::DarkGray));
+        assert_eq!(parse_color("#371b00"), Ok(Color::Rgb(55, 27, 0)));
+        assert_eq!(parse_color("208"), Ok(Color::Indexed(208)));
+        assert_eq!(parse_color("#37"), Err(ThemeError::Color(String::from("#37"))));
+        assert_eq!(parse_color("256"), Err(ThemeError::Color(String::from("256"))));
+        assert_eq!(parse_color("#zz1b00"), Err(ThemeError::Color(String::from("#zz1b00"))));
+    }
+
+    #[test]
+    fn themes_complete_their_base() {
+        let theme = parse_theme(r##"{
+            "base": "ascii",
+            "water": { "symbol": "≈", "fg": "white", "bg": "#0000ff" },
+            "bonuses": { "ImproveSpeed": { "symbol": ">" } },
+            "maluses": { "Custom:Trap": { "symbol": "?", "modifiers": ["bold", "reversed"] } },
+            "players": [{ "symbol": "@", "fg": "yellow" }],
+            "danger": "red"
+        }"##).unwrap();
+        let glyphs = &theme.glyphs;
+        let ascii = Glyphs::ascii();
+        assert_eq!(glyphs.water, Glyph::new("≈", Style::default().fg(Color::White).bg(Color::Rgb(0, 0, 255))));
+        assert_eq!(glyphs.block, ascii.block);
+        assert_eq!(glyphs.bonus(&Bonus::ImproveSpeed).symbol, ">");
+        assert_eq!(glyphs.bonus(&Bonus::MoreBombs), &ascii.bonus);
+        assert_eq!(glyphs.malus(&Malus::Custom(String::from("Trap"))).style.modifier, Modifier::BOLD | Modifier::REVERSED);
+        assert_eq!(glyphs.malus(&Malus::Custom(String::from("Other"))), &ascii.malus);
+        // Other slots are kept
+        assert_eq!(glyphs.players.len(), 4);
+        assert_eq!(glyphs.players[0], Glyph::new("@", ascii.players[0].style.fg(Color::Yellow)));
+        assert_eq!(glyphs.players[1], ascii.players[1]);
+        assert_eq!(glyphs.danger, Color::Red);
+        assert_eq!(theme.compact, ascii);
+    }
+
+    #[test]
+    fn compact_glyphs_follow_the_theme() {
+        assert_eq!(parse_theme("{}").unwrap().compact, Glyphs::colors16());
+        assert_eq!(parse_theme(r#"{ "base": "ascii" }"#).unwrap().compact, Glyphs::ascii());
+        let theme = parse_theme(r#"{
+            "bomb": { "symbol": "💣" },
+            "compact": { "bomb": { "symbol": "Q", "fg": "red" } }
+        }"#).unwrap();
+        assert_eq!(theme.compact.bomb, Glyph::new("Q", Glyphs::colors16().bomb.style.fg(Color::Red)));
+        assert_eq!(theme.compact.block, Glyphs::colors16().block);
+        assert_eq!(theme.glyphs(1), &theme.compact);
+        assert_eq!(theme.glyphs(2), &theme.glyphs);
+        let theme = parse_theme(r#"{ "compact": { "base": "ascii" } }"#).unwrap();
+        assert_eq!(theme.compact, Glyphs::ascii());
+        assert_eq!(parse_theme(r#"{ "compact": { "compact": {} } }"#), Err(ThemeError::NestedCompact));
+    }
+
+    #[test]
+    fn invalid_themes_are_refused() {
+        assert_eq!(parse_theme("{}"), Ok(Theme::default()));
+        assert_eq!(parse_theme(r#"{ "base": "neon" }"#), Err(ThemeError::UnknownBase(String::from("neon"))));
+        assert_eq!(parse_theme(r#"{ "bomb": { "fg": "purple" } }"#), Err(ThemeError::Color(String::from("purple"))));
+        assert_eq!(parse_theme(r#"{ "bomb": { "modifiers": ["shiny"] } }"#), Err(ThemeError::Modifier(String::from("shiny"))));
+        assert!(matches!(parse_theme(r#"{ "bombs": {} }"#), Err(ThemeError::Json(_))));
+        assert_eq!(parse_theme(r#"{ "bonuses": { "ImproveSped": {} } }"#), Err(ThemeError::UnknownItem(String::from("ImproveSped"))));
+        assert_eq!(parse_theme(r#"{ "maluses": { "ImproveSpeed": {} } }"#), Err(ThemeError::UnknownItem(String::from("ImproveSpeed"))));
+        assert_eq!(parse_theme(r#"{ "maluses": { "Custom:": {} } }"#), Err(ThemeError::UnknownItem(String::from("Custom:"))));
+        assert!(matches!(load_theme("/nonexistent/theme.json"), Err(ThemeError::Io(_))));
+    }
+
+    #[test]
+    fn builtin_themes_are_loaded_by_name() {
+        for name in &THEMES {
+            let theme = load_theme(name).unwrap();
+            assert!(theme.compact.fits(1));
+            assert_ne!(theme.glyphs.danger, Color::Reset);
+            assert_ne!(theme.glyphs.danger_soon, Color::Reset);
+        }
+        assert!(!Glyphs::default().fits(1));
+        assert!(Glyphs::default().fits(2));
+        assert!(Glyphs::ascii().fits(1));
+        assert!(Glyphs::colors16().fits(1));
+    }
+}
@@ -0,0 +1,239 @@
+/**
+ * Copyright (c) 2019, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+
+use bomberust_client::gen::item::{ Bonus, Malus };
+use bomberust_client::gen::map::Map;
+use bomberust_client::gen::utils::PlayerEffect;
+use bomberust_client::{ Glyphs, RoundResult };
+
+use std::time::{ Duration, Instant };
+use tui::style::{ Color, Modifier, Style };
+use tui::widgets::Text;
+
+/// Columns used by the side panel of the game
+pub const HUD_WIDTH: u16 = 32;
+
+/**
+ * @param glyphs    the theme of the map
+ * @param id        the player
+ * @return          the symbol drawn for this player on the map
+ */
+pub fn player_symbol(glyphs: &Glyphs, id: usize) -> &str {
+    glyphs.player(id).map_or("", |glyph| glyph.symbol.as_str())
+}
+
+/**
+ * @param names names of the players, by id. Can be empty
+ * @param id    the player
+ * @return      the name to show for this player
+ */
+pub fn player_name(names: &[String], id: usize) -> String {
+    match names.get(id) {
+        Some(name) if !name.is_empty() => name.clone(),
+        _ => format!("Player {}", id + 1),
+    }
+}
+
+/**
+ * Describe the players of a game for the side panel
+ * @param map           the game
+ * @param names         names of the players, by id. Can be empty
+ * @param glyphs        the theme of the map
+ * @param effects       active effects of the players, by id
+ * @param linked_id     our player, highlighted
+ * @param now           to compute the remaining time of the effects
+ * @return              the lines of the panel
+ */
+pub fn player_lines(map: &Map, names: &[String], glyphs: &Glyphs, effects: &[Vec<PlayerEffect>],
+                    linked_id: Option<u64>, now: Instant) -> Vec<Text<'static>> {
+    let mut lines = Vec::new();
+    for (id, player) in map.players.iter().enumerate() {
+        let local = linked_id == Some(id as u64);
+        let mut title = format!("{} {}", player_symbol(glyphs, id), player_name(names, id));
+        if local {
+            title.push_str(" (you)");
+        }
+        let mut style = if local {
+            Style::default().fg(Color::Yellow).modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::White)
+        };
+        if player.dead {
+            title.push_str(" - dead");
+            style = style.fg(Color::DarkGray).modifier(Modifier::CROSSED_OUT);
+        }
+        lines.push(Text::styled(format!("{}\n", title), style));
+        if player.dead {
+            lines.push(Text::raw("\n"));
+            continue;
+        }
+
+        lines.push(Text::raw(format!("  bombs {}  radius {}  speed {:.1}x\n",
+                                     player.bomb, player.radius, player.speed_factor as f32 / 1000.0)));
+        for effect in effects.get(id).map(|e| e.as_slice()).unwrap_or_default() {
+            if let Some(line) = effect_line(effect, now) {
+                lines.push(line);
+            }
+        }
+        lines.push(Text::raw("\n"));
+    }
+    lines
+}
+
+/**
+ * Describe the end of a game for the results screen
+ * @param result    how the game ended
+ * @param names     names of the players, by id. Can be empty
+ * @param glyphs    the theme of the map
+ * @param linked_id our player, highlighted
+ * @return          the lines of the screen
+ */
+pub fn result_lines(result: &RoundResult, names: &[String], glyphs: &Glyphs, linked_id: Option<u64>) -> Vec<Text<'static>> {
+    let title_style = Style::default().fg(Color::Yellow).modifier(Modifier::BOLD);
+    let title = match result.winner {
+        Some(id) if linked_id == Some(id) => String::from("You won!"),
+        Some(id) => format!("{} {} won!", player_symbol(glyphs, id as usize), player_name(names, id as usize)),
+        None => String::from("Draw!"),
+    };
+    let mut lines = vec![
+        Text::styled(format!("{}\n\n", title), title_style),
+        Text::raw(format!("Duration: {}\n\n", format_duration(result.duration))),
+    ];
+    for (rank, id) in result.ranking.iter().enumerate() {
+        let line = format!("{}. {} {}\n", rank + 1, player_symbol(glyphs, *id as usize), player_name(names, *id as usize));
+        if linked_id == Some(*id) {
+            lines.push(Text::styled(line, title_style));
+        } else {
+            lines.push(Text::raw(line));
+        }
+    }
+    lines.push(Text::raw("\nEnter: back to the room    Esc: back to the lobby\n"));
+    lines
+}
+
+/**
+ * @param duration  the duration
+ * @return          the duration as minutes:seconds
+ */
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+/**
+ * @param effect    the effect
+ * @param now       to compute the remaining time
+ * @return          the line of the effect, None if expired
+ */
+fn effect_line(effect: &PlayerEffect, now: Instant) -> Option<Text<'static>> {
+    let remaining = match effect.end {
+        Some(end) if end <= now => return None,
+        Some(end) => format!(" ({}s)", (end - now).as_secs() + 1),
+        None => String::new(),
+    };
+    if let Some(bonus) = &effect.bonus {
+        let line = format!("  + {}{}\n", bonus_name(bonus), remaining);
+        Some(Text::styled(line, Style::default().fg(Color::Green)))
+    } else if let Some(malus) = &effect.malus {
+        let line = format!("  - {}{}\n", malus_name(malus), remaining);
+        Some(Text::styled(line, Style::default().fg(Color::Red)))
+    } else {
+        None
+    }
+}
+
+fn bonus_name(bonus: &Bonus) -> String {
+    match bonus {
+        Bonus::ImproveBombRadius => String::from("Bomb radius"),
+        Bonus::PunchBombs => String::from("Punch bombs"),
+        Bonus::ImproveSpeed => String::from("Speed"),
+        Bonus::RepelBombs => String::from("Repel bombs"),
+        Bonus::MoreBombs => String::from("More bombs"),
+        Bonus::Custom(name) => name.clone(),
+    }
+}
+
+fn malus_name(malus: &Malus) -> String {
+    match malus {
+        Malus::Slow => String::from("Slow"),
+        Malus::UltraFast => String::from("Ultra fast"),
+        Malus::SpeedBomb => String::from("Speed bombs"),
+        Malus::DropBombs => String::from("Drop bombs"),
+        Malus::InvertedControls => String::from("Inverted controls"),
+        Malus::Custom(name) => name.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn content(lines: &[Text]) -> String {
+        lines.iter().map(|line| match line {
+            Text::Raw(text) => text.to_string(),
+            Text::Styled(text, _) => text.to_string(),
+        }).collect()
+    }
+
+    #[test]
+    fn players_and_stats() {
+        let mut map = Map::new(11, 11);
+        map.players[1].dead = true;
+        let names = vec![String::from("alice")];
+        let lines = player_lines(&map, &names, &Glyphs::default(), &[], Some(0), Instant::now());
+        let content = content(&lines);
+        assert!(content.starts_with("🐧 alice (you)\n  bombs"));
+        assert!(content.contains("🐥 Player 2 - dead\n"));
+        match &lines[0] {
+            Text::Styled(_, style) => assert_eq!(style.fg, Color::Yellow),
+            _ => panic!("The local player is not highlighted"),
+        }
+    }
+
+    #[test]
+    fn results() {
+        let result = RoundResult { winner: Some(1), ranking: vec![1, 0], duration: Duration::from_secs(83) };
+        let lines = result_lines(&result, &[String::from("alice"), String::from("bob")], &Glyphs::default(), Some(0));
+        assert!(content(&lines).starts_with("🐥 bob won!\n\nDuration: 1:23\n\n1. 🐥 bob\n2. 🐧 alice\n"));
+        let draw = RoundResult { winner: None, ranking: Vec::new(), duration: Duration::from_secs(5) };
+        assert!(content(&result_lines(&draw, &[], &Glyphs::default(), Some(0))).starts_with("Draw!"));
+    }
+
+    #[test]
+    fn effects_with_remaining_time() {
+        let map = Map::new(11, 11);
+        let now = Instant::now();
+        let mut effects = vec![Vec::new(); map.players.len()];
+        effects[0].push(PlayerEffect { end: Some(now + Duration::from_millis(2500)), bonus: Some(Bonus::ImproveSpeed), malus: None });
+        effects[0].push(PlayerEffect { end: None, bonus: None, malus: Some(Malus::InvertedControls) });
+        effects[0].push(PlayerEffect { end: Some(now), bonus: Some(Bonus::MoreBombs), malus: None });
+        let content = content(&player_lines(&map, &[], &Glyphs::default(), &effects, None, now));
+        assert!(content.contains("  + Speed (3s)\n  - Inverted controls\n"));
+        assert!(!content.contains("More bombs"));
+    }
+}
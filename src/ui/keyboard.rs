@@ -0,0 +1,175 @@
+/**
+ * Copyright (c) 2019, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+
+use crate::util::Event;
+
+use std::io::{ self, Write };
+use termion::event::Key;
+
+// Keyboard mode reporting key releases, from the kitty keyboard protocol
+// (https://sw.kovidgoyal.net/kitty/keyboard-protocol/). Terminals without
+// support ignore it and keep sending the usual bytes, with auto-repeat.
+// Flags: disambiguate escape codes (1), report event types (2) and report all
+// keys as escape codes (8), else the release of text keys is not reported.
+const ENABLE_KEY_RELEASES: &str = "\x1b[>11u";
+const DISABLE_KEY_RELEASES: &str = "\x1b[<u";
+
+// Modifiers bits, once 1 is subtracted from the transmitted value
+const SHIFT: u32 = 1;
+const ALT: u32 = 2;
+const CTRL: u32 = 4;
+
+// Event types
+const RELEASE: u32 = 3;
+
+/**
+ * Ask the terminal to report key presses and releases as escape codes
+ * @param out   the terminal
+ */
+pub fn enable_key_releases<W: Write>(out: &mut W) -> io::Result<()> {
+    out.write_all(ENABLE_KEY_RELEASES.as_bytes())?;
+    out.flush()
+}
+
+/**
+ * Restore the keyboard mode active before enable_key_releases
+ * @param out   the terminal
+ */
+pub fn disable_key_releases<W: Write>(out: &mut W) -> io::Result<()> {
+    out.write_all(DISABLE_KEY_RELEASES.as_bytes())?;
+    out.flush()
+}
+
+/**
+ * Parse a key event of the kitty keyboard protocol, not understood by termion.
+ * Repeats are reported as presses.
+ * @param seq   the escape sequence
+ * @return      the event, None if seq is not a key event
+ */
+pub fn parse_key_event(seq: &[u8]) -> Option<Event<Key>> {
+    let seq = std::str::from_utf8(seq).ok()?;
+    let params = seq.strip_prefix("\x1b[")?;
+    let terminator = params.chars().last()?;
+    let params = &params[..params.len() - terminator.len_utf8()];
+    let mut fields = params.split(';');
+    // Alternate keys may follow the key code, after a ':'
+    let code = fields.next()?.split(':').next()?;
+    // Modifiers, then the event type: 1 press (default), 2 repeat, 3 release
+    let mut modifiers = fields.next().unwrap_or("1").split(':');
+    let active = modifiers.next()?.parse::<u32>().ok()?.checked_sub(1)?;
+    let event_type = match modifiers.next() {
+        Some(event_type) => event_type.parse::<u32>().ok()?,
+        None => 1,
+    };
+    let key = match terminator {
+        'u' => code_key(code.parse().ok()?, active)?,
+        'A' => Key::Up,
+        'B' => Key::Down,
+        'C' => Key::Right,
+        'D' => Key::Left,
+        'H' => Key::Home,
+        'F' => Key::End,
+        '~' => match code {
+            "2" => Key::Insert,
+            "3" => Key::Delete,
+            "5" => Key::PageUp,
+            "6" => Key::PageDown,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    if event_type == RELEASE {
+        Some(Event::Release(key))
+    } else {
+        Some(Event::Input(key))
+    }
+}
+
+/**
+ * @param code      unicode code point of the key
+ * @param modifiers active modifiers
+ * @return          the termion key, None for keys without equivalent (modifiers alone, media keys...)
+ */
+fn code_key(code: u32, modifiers: u32) -> Option<Key> {
+    let key = match code {
+        9 => Key::Char('\t'),
+        13 => Key::Char('\n'),
+        27 => Key::Esc,
+        127 => Key::Backspace,
+        // Functional keys without legacy encoding are in the private use area
+        0xE000..=0xF8FF => return None,
+        _ => {
+            let c = std::char::from_u32(code)?;
+            if modifiers & CTRL != 0 {
+                Key::Ctrl(c)
+            } else if modifiers & ALT != 0 {
+                Key::Alt(c)
+            } else if modifiers & SHIFT != 0 {
+                Key::Char(c.to_uppercase().next()?)
+            } else {
+                Key::Char(c)
+            }
+        }
+    };
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(seq: &str) -> Option<Event<Key>> {
+        parse_key_event(seq.as_bytes())
+    }
+
+    #[test]
+    fn text_keys() {
+        assert_eq!(parse("\x1b[119u"), Some(Event::Input(Key::Char('w'))));
+        assert_eq!(parse("\x1b[119;1:2u"), Some(Event::Input(Key::Char('w'))));
+        assert_eq!(parse("\x1b[119;1:3u"), Some(Event::Release(Key::Char('w'))));
+        assert_eq!(parse("\x1b[97;2u"), Some(Event::Input(Key::Char('A'))));
+        assert_eq!(parse("\x1b[99;5u"), Some(Event::Input(Key::Ctrl('c'))));
+        assert_eq!(parse("\x1b[32u"), Some(Event::Input(Key::Char(' '))));
+    }
+
+    #[test]
+    fn functional_keys() {
+        assert_eq!(parse("\x1b[27u"), Some(Event::Input(Key::Esc)));
+        assert_eq!(parse("\x1b[13;1:3u"), Some(Event::Release(Key::Char('\n'))));
+        assert_eq!(parse("\x1b[1;1:3A"), Some(Event::Release(Key::Up)));
+        assert_eq!(parse("\x1b[3;1:1~"), Some(Event::Input(Key::Delete)));
+        // Left shift alone
+        assert_eq!(parse("\x1b[57441;2u"), None);
+    }
+
+    #[test]
+    fn not_key_events() {
+        assert_eq!(parse("\x1b[?11u"), None);
+        assert_eq!(parse("\x1b[12;24R"), None);
+        assert_eq!(parse("w"), None);
+    }
+}
@@ -27,20 +27,19 @@
 
 use std::sync::{Arc, Mutex};
 use std::io::{stdin,stdout,Write};
-use serde::Serialize;
-use rmps::Serializer;
 
-use super::super::gen::utils::Direction;
-use super::super::net::msg::*;
+use bomberust_client::gen::utils::Direction;
+use bomberust_client::net::msg::ClientCommand;
+use bomberust_client::Client;
 
 pub struct KeyHandler {
-    pub send_buf: Arc<Mutex<Option<Vec<u8>>>>,
+    pub client: Arc<Mutex<Client>>,
 }
 
 impl KeyHandler {
-    pub fn new(send_buf: Arc<Mutex<Option<Vec<u8>>>>) -> KeyHandler {
+    pub fn new(client: Arc<Mutex<Client>>) -> KeyHandler {
         KeyHandler {
-            send_buf
+            client
         }
     }
 
@@ -55,17 +54,10 @@ impl KeyHandler {
         s
     }
 
-    fn send_rtp(&mut self, send: &mut Vec<u8>) {
-        if send.len() > (2 as usize).pow(16) {
-            error!("Can't send RTP packet because buffer is too long");
-            return;
+    fn send(&mut self, cmd: ClientCommand) {
+        if let Err(e) = self.client.lock().unwrap().send(cmd) {
+            println!("Can't send command: {}", e);
         }
-        let len = send.len() as u16;
-        let mut send_buf : Vec<u8> = Vec::with_capacity(65536);
-        send_buf.push((len >> 8) as u8);
-        send_buf.push((len as u16 % (2 as u16).pow(8)) as u8);
-        send_buf.append(send);
-        *self.send_buf.lock().unwrap() = Some(send_buf);
     }
 
     fn print_help() {
@@ -74,7 +66,7 @@ impl KeyHandler {
         println!(" j [room]   join a room");
         println!(" l          leave the current room");
         println!(" g          start a new game");
-        println!("");
+        println!();
         println!("Possible commands (in game):");
         println!("Send w,a,s,d to move or SPACE to put a bomb");
         println!("Send e to use special");
@@ -84,15 +76,12 @@ impl KeyHandler {
         println!("WELCOME TO BOMBER RUST v0.0!");
         let mut s = String::new();
         print!("Player name: ");
-        let mut buf = Vec::new();
         while s.is_empty() {
             let _ = stdout().flush();
             stdin().read_line(&mut s).expect("Did not enter a correct string");
             s = KeyHandler::clean_string(s);
             if !s.is_empty() {
-                let msg = PlayerMsg::new(s.clone());
-                msg.serialize(&mut Serializer::new(&mut buf)).unwrap();
-                self.send_rtp(&mut buf);
+                self.send(ClientCommand::Player { name: s.clone() });
             }
         }
         s = String::new();
@@ -102,36 +91,31 @@ impl KeyHandler {
             stdin().read_line(&mut s).expect("Did not enter a correct string");
             s = KeyHandler::clean_string(s);
             if !s.is_empty() {
-                if s == "c" {
-                    let msg = Msg::new(String::from("create"));
-                    msg.serialize(&mut Serializer::new(&mut buf)).unwrap();
+                let cmd = if s == "c" {
+                    Some(ClientCommand::Create)
                 } else if s == "l" {
-                    let msg = Msg::new(String::from("leave"));
-                    msg.serialize(&mut Serializer::new(&mut buf)).unwrap();
+                    Some(ClientCommand::Leave)
                 } else if s == "g" {
-                    let msg = Msg::new(String::from("launch"));
-                    msg.serialize(&mut Serializer::new(&mut buf)).unwrap();
+                    Some(ClientCommand::Launch)
                 } else if s == " " {
-                    let msg = Msg::new(String::from("bomb"));
-                    msg.serialize(&mut Serializer::new(&mut buf)).unwrap();
+                    Some(ClientCommand::Bomb)
                 } else if s.starts_with("j") {
                     let room: u64 = String::from(&s[2..]).parse().unwrap_or(0);
-                    let msg = JoinMsg::new(room);
-                    msg.serialize(&mut Serializer::new(&mut buf)).unwrap();
+                    Some(ClientCommand::Join { room })
                 } else if s == "a" {
-                    let msg = MoveMsg::new(Direction::West);
-                    msg.serialize(&mut Serializer::new(&mut buf)).unwrap();
+                    Some(ClientCommand::Move { direction: Direction::West, seq: None })
                 } else if s == "s" {
-                    let msg = MoveMsg::new(Direction::South);
-                    msg.serialize(&mut Serializer::new(&mut buf)).unwrap();
+                    Some(ClientCommand::Move { direction: Direction::South, seq: None })
                 } else if s == "d" {
-                    let msg = MoveMsg::new(Direction::East);
-                    msg.serialize(&mut Serializer::new(&mut buf)).unwrap();
+                    Some(ClientCommand::Move { direction: Direction::East, seq: None })
                 } else if s == "w" {
-                    let msg = MoveMsg::new(Direction::North);
-                    msg.serialize(&mut Serializer::new(&mut buf)).unwrap();
+                    Some(ClientCommand::Move { direction: Direction::North, seq: None })
+                } else {
+                    None
+                };
+                if let Some(cmd) = cmd {
+                    self.send(cmd);
                 }
-                self.send_rtp(&mut buf);
                 s = String::new();
             }
         }
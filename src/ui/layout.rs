@@ -0,0 +1,139 @@
+/**
+ * Copyright (c) 2019, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+
+use crate::ui::HUD_WIDTH;
+
+use tui::layout::Rect;
+
+/// Size of a square of the map (columns, rows), from the largest to the most compact.
+/// Emojis need 2 columns, so the 1x1 size uses one column glyphs
+pub const CELL_SIZES: [(u16, u16); 5] = [(5, 5), (4, 4), (3, 3), (2, 1), (1, 1)];
+
+/**
+ * Where the game is drawn in the terminal
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GameLayout {
+    pub cell_w: u16, // Columns of a square
+    pub cell_h: u16, // Rows of a square
+    pub map: Rect, // Area of the map, centered
+    pub hud: Option<Rect>, // Side panel, if there is room left for it
+}
+
+impl GameLayout {
+    /**
+     * @param size  the terminal, including the borders of the game block
+     * @param map_w width of the map, in squares
+     * @param map_h height of the map, in squares
+     * @return      the largest squares the map fits with, keeping room for the
+     *              side panel if possible. None if the terminal is too small
+     */
+    pub fn new(size: Rect, map_w: usize, map_h: usize) -> Option<GameLayout> {
+        let inner = Rect::new(size.x + 1, size.y + 1, size.width.saturating_sub(2), size.height.saturating_sub(2));
+        let with_hud = GameLayout::fit(inner, inner.width.saturating_sub(HUD_WIDTH + 1), map_w, map_h)
+            .map(|layout| GameLayout {
+                hud: Some(Rect::new(inner.x + inner.width - HUD_WIDTH, inner.y, HUD_WIDTH, inner.height)),
+                ..layout
+            });
+        with_hud.or_else(|| GameLayout::fit(inner, inner.width, map_w, map_h))
+    }
+
+    /**
+     * @param inner the area inside the borders
+     * @param width columns left for the map
+     * @param map_w width of the map, in squares
+     * @param map_h height of the map, in squares
+     * @return      the layout with the largest squares fitting, without side panel
+     */
+    fn fit(inner: Rect, width: u16, map_w: usize, map_h: usize) -> Option<GameLayout> {
+        CELL_SIZES.iter()
+            .find(|(w, h)| {
+                let fits = |squares: usize, cell: u16, room: u16| squares.checked_mul(cell as usize).is_some_and(|len| len <= room as usize);
+                fits(map_w, *w, width) && fits(map_h, *h, inner.height)
+            })
+            .map(|&(cell_w, cell_h)| {
+                let (map_width, map_height) = (map_w as u16 * cell_w, map_h as u16 * cell_h);
+                GameLayout {
+                    cell_w,
+                    cell_h,
+                    map: Rect::new(inner.x + (width - map_width) / 2, inner.y + (inner.height - map_height) / 2, map_width, map_height),
+                    hud: None,
+                }
+            })
+    }
+
+    /**
+     * @param map_w width of the map, in squares
+     * @param map_h height of the map, in squares
+     * @return      the smallest terminal (columns, rows) the map can be drawn in
+     */
+    pub fn min_size(map_w: usize, map_h: usize) -> (usize, usize) {
+        (map_w.saturating_add(2), map_h.saturating_add(2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn squares_shrink_with_the_terminal() {
+        let layout = GameLayout::new(Rect::new(0, 0, 120, 40), 11, 11).unwrap();
+        assert_eq!((layout.cell_w, layout.cell_h), (3, 3));
+        assert_eq!(layout.hud, Some(Rect::new(87, 1, HUD_WIDTH, 38)));
+        // Centered left of the side panel
+        assert_eq!(layout.map, Rect::new(27, 3, 33, 33));
+
+        let layout = GameLayout::new(Rect::new(0, 0, 80, 24), 11, 11).unwrap();
+        assert_eq!((layout.cell_w, layout.cell_h), (2, 1));
+        assert!(layout.hud.is_some());
+
+        let layout = GameLayout::new(Rect::new(0, 0, 200, 80), 11, 11).unwrap();
+        assert_eq!((layout.cell_w, layout.cell_h), (5, 5));
+    }
+
+    #[test]
+    fn side_panel_is_dropped_before_the_map() {
+        let layout = GameLayout::new(Rect::new(0, 0, 30, 15), 13, 13).unwrap();
+        assert_eq!(layout.hud, None);
+        assert_eq!((layout.cell_w, layout.cell_h), (2, 1));
+
+        let layout = GameLayout::new(Rect::new(0, 0, 15, 15), 13, 13).unwrap();
+        assert_eq!((layout.cell_w, layout.cell_h), (1, 1));
+        assert_eq!(layout.map, Rect::new(1, 1, 13, 13));
+    }
+
+    #[test]
+    fn too_small_terminals_have_no_layout() {
+        assert_eq!(GameLayout::min_size(13, 13), (15, 15));
+        assert_eq!(GameLayout::new(Rect::new(0, 0, 14, 15), 13, 13), None);
+        assert_eq!(GameLayout::new(Rect::new(0, 0, 0, 0), 13, 13), None);
+        assert_eq!(GameLayout::new(Rect::new(0, 0, 15, 14), 13, 13), None);
+        assert_eq!(GameLayout::new(Rect::new(0, 0, 200, 80), usize::MAX, 2), None);
+        assert_eq!(GameLayout::min_size(usize::MAX, 13), (usize::MAX, 15));
+    }
+}
@@ -25,10 +25,17 @@
  * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  **/
 
-mod client;
+mod hud;
+mod keyboard;
+// Line based client, before the tui one
+#[allow(dead_code)]
 mod keyhandler;
+mod layout;
+mod movement;
 mod tuiclient;
 
-pub use client::Client;
-pub use keyhandler::KeyHandler;
+pub use hud::{ player_lines, result_lines, HUD_WIDTH };
+pub use keyboard::{ disable_key_releases, enable_key_releases, parse_key_event };
+pub use layout::GameLayout;
+pub use movement::{ HeldMove, DEFAULT_MOVE_RATE };
 pub use tuiclient::TuiClient;
\ No newline at end of file
@@ -0,0 +1,254 @@
+/**
+ * Copyright (c) 2019, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+
+use bomberust_client::gen::utils::Direction;
+
+use std::time::{ Duration, Instant };
+
+/// Default time between two moves while a direction is held
+pub const DEFAULT_MOVE_RATE: Duration = Duration::from_millis(50);
+/// Without key releases, a single press is held for this long, so the terminal
+/// auto-repeat (usually starting after 250 to 600 ms) can confirm it is held
+const REPEAT_DELAY: Duration = Duration::from_millis(700);
+/// Without key releases, the key is released when the auto-repeat stops for this long
+const RELEASE_TIMEOUT: Duration = Duration::from_millis(150);
+/// Without key releases, a press of the same key sooner than this is a new tap,
+/// the terminal auto-repeat doesn't start that fast
+const MIN_REPEAT_DELAY: Duration = Duration::from_millis(200);
+
+/**
+ * Turn key presses and releases into moves sent at a fixed rate while a
+ * direction is held, whatever the auto-repeat rate of the terminal.
+ * When the terminal doesn't report key releases, a direction is held as long
+ * as the auto-repeat of the terminal sends it.
+ */
+pub struct HeldMove {
+    rate: Duration, // Time between two moves
+    releases: bool, // If the terminal reports key releases
+    held: Vec<Direction>, // Held directions, the last one is followed
+    repeating: bool, // If the auto-repeat confirmed the direction is held
+    last_press: Instant,
+    next_move: Instant,
+}
+
+impl HeldMove {
+    /**
+     * @param rate  time between two moves while a direction is held
+     */
+    pub fn new(rate: Duration) -> HeldMove {
+        let now = Instant::now();
+        HeldMove {
+            rate,
+            releases: false,
+            held: Vec::new(),
+            repeating: false,
+            last_press: now,
+            next_move: now,
+        }
+    }
+
+    /**
+     * A direction key was pressed, or repeated
+     * @param direction the direction pressed
+     * @param now       time of the press
+     * @return          the move to send now
+     */
+    pub fn press(&mut self, direction: Direction, now: Instant) -> Option<Direction> {
+        let since_last_press = now.saturating_duration_since(self.last_press);
+        self.last_press = now;
+        if self.held.last() == Some(&direction) {
+            if self.releases || self.repeating || since_last_press >= MIN_REPEAT_DELAY {
+                self.repeating = true;
+                return None;
+            }
+            // Tapped again
+            self.next_move = now + self.rate;
+            return Some(direction);
+        }
+        if !self.releases {
+            // Only the last key is repeated by the terminal
+            self.held.clear();
+        }
+        self.held.retain(|held| *held != direction);
+        self.held.push(direction);
+        self.repeating = self.releases;
+        self.next_move = now + self.rate;
+        Some(direction)
+    }
+
+    /**
+     * A direction key was released. Only reported by some terminals, the auto-repeat
+     * is not needed anymore after the first release
+     * @param direction the direction released
+     * @param now       time of the release
+     */
+    pub fn release(&mut self, direction: Direction, now: Instant) {
+        self.releases = true;
+        let followed = self.held.last() == Some(&direction);
+        self.held.retain(|held| *held != direction);
+        if followed && !self.held.is_empty() {
+            // Back to the previous direction, still held
+            self.next_move = now;
+        }
+        self.repeating = true;
+    }
+
+    /**
+     * Release every direction
+     */
+    pub fn stop(&mut self) {
+        self.held.clear();
+        self.repeating = false;
+    }
+
+    /**
+     * @param now   current time
+     * @return      the move to send now, if the direction is still held
+     */
+    pub fn poll(&mut self, now: Instant) -> Option<Direction> {
+        let direction = *self.held.last()?;
+        if !self.releases {
+            let timeout = if self.repeating { RELEASE_TIMEOUT } else { REPEAT_DELAY };
+            if now.duration_since(self.last_press) > timeout {
+                self.stop();
+                return None;
+            }
+            if !self.repeating {
+                return None;
+            }
+        }
+        if now < self.next_move {
+            return None;
+        }
+        self.next_move += self.rate;
+        if self.next_move < now {
+            // Late, don't send a burst of moves
+            self.next_move = now + self.rate;
+        }
+        Some(direction)
+    }
+
+    /**
+     * @return when poll should be called for the next move
+     */
+    pub fn deadline(&self) -> Option<Instant> {
+        if self.held.is_empty() || !self.repeating {
+            None
+        } else {
+            Some(self.next_move)
+        }
+    }
+}
+
+impl Default for HeldMove {
+    fn default() -> HeldMove {
+        HeldMove::new(DEFAULT_MOVE_RATE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: Duration = Duration::from_millis(50);
+
+    fn ms(start: Instant, ms: u64) -> Instant {
+        start + Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn tap_moves_once() {
+        let start = Instant::now();
+        let mut held = HeldMove::new(RATE);
+        assert_eq!(held.press(Direction::North, start), Some(Direction::North));
+        for t in (0..1000).step_by(10) {
+            assert_eq!(held.poll(ms(start, t)), None);
+        }
+        // Released, the next press moves again
+        assert_eq!(held.press(Direction::North, ms(start, 1000)), Some(Direction::North));
+    }
+
+    #[test]
+    fn double_tap_moves_twice() {
+        let start = Instant::now();
+        let mut held = HeldMove::new(RATE);
+        assert_eq!(held.press(Direction::West, start), Some(Direction::West));
+        assert_eq!(held.press(Direction::West, ms(start, 120)), Some(Direction::West));
+        for t in (120..1000).step_by(10) {
+            assert_eq!(held.poll(ms(start, t)), None);
+        }
+    }
+
+    #[test]
+    fn auto_repeat_moves_at_rate() {
+        let start = Instant::now();
+        let mut held = HeldMove::new(RATE);
+        assert_eq!(held.press(Direction::East, start), Some(Direction::East));
+        // Terminal auto-repeat: after 500 ms, then every 30 ms
+        let mut moves = 1;
+        for t in (0..=1000).step_by(10) {
+            if t >= 500 && t % 30 == 20 {
+                assert_eq!(held.press(Direction::East, ms(start, t)), None);
+            }
+            if held.poll(ms(start, t)).is_some() {
+                moves += 1;
+            }
+        }
+        // One move, then one each 50 ms once the repeat started
+        assert_eq!(moves, 1 + 11);
+        // Auto-repeat stopped: released
+        assert_eq!(held.poll(ms(start, 1200)), None);
+        assert_eq!(held.deadline(), None);
+    }
+
+    #[test]
+    fn release_stops_moves() {
+        let start = Instant::now();
+        let mut held = HeldMove::new(RATE);
+        held.release(Direction::West, start);
+        assert_eq!(held.press(Direction::West, start), Some(Direction::West));
+        assert_eq!(held.poll(ms(start, 20)), None);
+        assert_eq!(held.poll(ms(start, 50)), Some(Direction::West));
+        assert_eq!(held.poll(ms(start, 100)), Some(Direction::West));
+        held.release(Direction::West, ms(start, 120));
+        assert_eq!(held.poll(ms(start, 150)), None);
+    }
+
+    #[test]
+    fn release_resumes_previous_direction() {
+        let start = Instant::now();
+        let mut held = HeldMove::new(RATE);
+        held.release(Direction::West, start);
+        assert_eq!(held.press(Direction::West, start), Some(Direction::West));
+        assert_eq!(held.press(Direction::North, ms(start, 10)), Some(Direction::North));
+        assert_eq!(held.poll(ms(start, 60)), Some(Direction::North));
+        held.release(Direction::North, ms(start, 70));
+        assert_eq!(held.poll(ms(start, 70)), Some(Direction::West));
+        assert_eq!(held.poll(ms(start, 100)), None);
+        assert_eq!(held.poll(ms(start, 120)), Some(Direction::West));
+    }
+}
@@ -0,0 +1,1207 @@
+use bomberust_client::{ danger_zone, load_theme, Client, Flame, MapWidget, Theme, OUTBOUND_QUEUE_SIZE };
+use bomberust_client::gen::utils::Direction;
+use bomberust_client::net::{ ConnectionState, HeartbeatPolicy, ReconnectPolicy, ServerAddress, TlsClient, TlsClientConfig, HANDSHAKE_TIMEOUT };
+use bomberust_client::net::msg::ClientCommand;
+use crate::ui::{ disable_key_releases, enable_key_releases, player_lines, result_lines, GameLayout, HeldMove, DEFAULT_MOVE_RATE };
+use crate::util::{ Config, Event, Events };
+
+use futures::sync::mpsc;
+use std::convert::TryFrom;
+use std::fs::{ self, File };
+use std::io::{ stdout, Write };
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{ Duration, Instant, SystemTime };
+use termion::event::Key;
+use termion::input::MouseTerminal;
+use termion::raw::IntoRawMode;
+use termion::screen::AlternateScreen;
+use tui::backend::TermionBackend;
+use tui::style::{ Style, Color, Modifier };
+use tui::Terminal;
+use tui::layout::{Alignment, Rect};
+use tui::widgets::{ Block, Borders, Paragraph, SelectableList, Text, Widget };
+use tui::widgets::canvas::Canvas;
+use tui::terminal::Frame;
+
+// TODO Layout
+
+/// Time between two frames. Independent of the events tick so moves stay smooth
+const FRAME_TIME: Duration = Duration::from_millis(33);
+/// How long "You died" is shown before spectating the end of the game
+const DEATH_OVERLAY_TIME: Duration = Duration::from_secs(3);
+
+#[derive(PartialEq)]
+pub enum Location {
+    Splash,
+    ConfigureServer,
+    Lobby,
+    Room,
+    Game,
+    Results, // End of a game, until the player goes back to the room or lobby
+    TrustCertificate(String), // Fingerprint to confirm
+    CertificateMismatch { expected: String, presented: String },
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ServerInfo {
+    pub name: String,
+    pub address: String,
+    #[serde(default)]
+    pub server_name: String, // Name in the certificate. Host of the address if empty
+    pub certificate: String,
+    #[serde(default)]
+    pub client_certificate: String, // PEM chain sent when the server asks for a client certificate
+    #[serde(default)]
+    pub client_key: String, // PEM private key of client_certificate
+    #[serde(default)]
+    pub trust_on_first_use: bool, // Pin the certificate of the first connection instead of using CAs
+    #[serde(default)]
+    pub fingerprint: String, // Pinned SHA-256 fingerprint
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ClientConfig {
+    servers: Vec<ServerInfo>,
+    default_playername: String,
+    #[serde(default = "default_max_missed_pongs")]
+    max_missed_pongs: u32, // The connection is lost after this many unanswered pings. 0 to disable
+    #[serde(default = "default_move_rate_ms")]
+    move_rate_ms: u64, // Time between two moves while a direction is held
+    #[serde(default)]
+    danger_zone: bool, // Tint the squares the bombs will hit. Toggled with 'z' in game
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    theme: String, // Built-in theme (emoji, ascii, 16colors) or path of a JSON theme. Emoji if empty
+}
+
+fn default_max_missed_pongs() -> u32 {
+    HeartbeatPolicy::default().max_missed
+}
+
+fn default_move_rate_ms() -> u64 {
+    DEFAULT_MOVE_RATE.as_millis() as u64
+}
+
+// TODO separate by layout
+pub struct TuiClient {
+    location: Location,
+    selected_item: Option<usize>,
+    items_len: usize,
+    new_server_info: Option<ServerInfo>,
+    config: ClientConfig,
+    client_thread: Option<thread::JoinHandle<()>>,
+    connected_item: Option<String>,
+    server_state: Arc<Mutex<Option<ConnectionState>>>,
+    last_error: String,
+    room_to_join: String,
+    client: Option<Arc<Mutex<Client>>>,
+    next_frame: Instant, // Events are handled until this deadline, then a new frame is drawn
+    held_move: HeldMove,
+    key_releases: bool, // If the terminal was asked to report key releases
+    theme: Theme, // How the map is drawn
+}
+
+impl TuiClient {
+    pub fn new() -> TuiClient {
+        let mut config = ClientConfig {
+            servers: Vec::new(),
+            default_playername: String::new(),
+            max_missed_pongs: default_max_missed_pongs(),
+            move_rate_ms: default_move_rate_ms(),
+            danger_zone: false,
+            theme: String::new(),
+        };
+        if Path::new("config.json").is_file() {
+            let content = fs::read_to_string("config.json").unwrap_or_default();
+            config = serde_json::from_str(&content).unwrap_or(ClientConfig {
+                servers: Vec::new(),
+                default_playername: String::new(),
+                max_missed_pongs: default_max_missed_pongs(),
+                move_rate_ms: default_move_rate_ms(),
+                danger_zone: false,
+                theme: String::new(),
+            });
+        }
+        let held_move = HeldMove::new(Duration::from_millis(config.move_rate_ms));
+        let theme = if config.theme.is_empty() {
+            Theme::default()
+        } else {
+            load_theme(&config.theme).unwrap_or_else(|e| {
+                warn!("Can't load the theme {}: {}", config.theme, e);
+                Theme::default()
+            })
+        };
+        TuiClient {
+            location: Location::Splash,
+            selected_item: Some(0),
+            items_len: 2,
+            new_server_info: None,
+            config,
+            client_thread: None,
+            connected_item: None,
+            server_state: Arc::new(Mutex::new(None)),
+            last_error: String::new(),
+            room_to_join: String::new(),
+            client: None,
+            next_frame: Instant::now(),
+            held_move,
+            key_releases: false,
+            theme,
+        }
+    }
+
+    pub fn render(&mut self) -> Result<(), failure::Error> {
+        // Terminal initialization
+        let stdout = stdout().into_raw_mode()?;
+        let stdout = MouseTerminal::from(stdout);
+        let stdout = AlternateScreen::from(stdout);
+        let backend = TermionBackend::new(stdout);
+        let mut terminal = Terminal::new(backend)?;
+        terminal.hide_cursor()?;
+
+        let config = Config {
+            tick_rate: Duration::from_millis(100),
+            ..Default::default()
+        };
+        let events = Events::with_config(config);
+
+        loop {
+            self.next_frame = Instant::now() + FRAME_TIME;
+            // Key releases are only needed to move, other screens keep the usual keyboard
+            let in_game = self.location == Location::Game;
+            if in_game != self.key_releases {
+                let mode = if in_game {
+                    enable_key_releases(terminal.backend_mut())
+                } else {
+                    disable_key_releases(terminal.backend_mut())
+                };
+                if let Err(e) = mode {
+                    warn!("Can't change the keyboard mode: {}", e);
+                }
+                self.key_releases = in_game;
+                self.held_move.stop();
+            }
+            terminal.draw(|mut f| {
+                match self.location {
+                    Location::Splash => {
+                        self.render_splash(&mut f);
+                        self.draw_servers_list(&mut f);
+                    },
+                    Location::ConfigureServer => {
+                        self.render_splash(&mut f);
+                        self.configure_new_server(&mut f);
+                    },
+                    Location::Lobby => {
+                        self.render_splash(&mut f);
+                        self.draw_rooms_list(&mut f);
+                    },
+                    Location::Room => {
+                        self.render_splash(&mut f);
+                        self.draw_room_view(&mut f);
+                    },
+                    Location::Game => {
+                        self.render_game(&mut f);
+                    },
+                    Location::Results => {
+                        self.render_splash(&mut f);
+                        self.draw_results(&mut f);
+                    },
+                    Location::TrustCertificate(_) => {
+                        self.render_splash(&mut f);
+                        self.draw_trust_certificate(&mut f);
+                    },
+                    Location::CertificateMismatch { .. } => {
+                        self.render_splash(&mut f);
+                        self.draw_certificate_mismatch(&mut f);
+                    },
+                }
+                if let Some(ConnectionState::Reconnecting(attempt)) = *self.server_state.lock().unwrap() {
+                    TuiClient::draw_reconnecting(&mut f, attempt);
+                }
+            })?;
+
+            if self.in_session() {
+                let closed = match *self.server_state.lock().unwrap() {
+                    Some(ref state) => state.is_closed(),
+                    None => false,
+                };
+                if closed {
+                    // Reconnection failed, the servers list shows why
+                    self.selected_item = Some(0);
+                    self.location = Location::Splash;
+                }
+            }
+
+            match self.location {
+                Location::Splash => {
+                    if !self.events_splash(&events) {
+                        break;
+                    }
+                    if self.server_state.lock().unwrap().is_some() {
+                        // TODO partialeq
+                        let formatted = format!("{}", *self.server_state.lock().unwrap().as_ref().unwrap());
+                        if formatted == "Connected" {
+                            self.selected_item = Some(0);
+                            self.room_to_join = String::new();
+                            self.location = Location::Lobby;
+                        }
+                    }
+                    let mut server_state = self.server_state.lock().unwrap();
+                    let location = match server_state.as_ref() {
+                        Some(ConnectionState::UntrustedCertificate(fingerprint)) => {
+                            Some(Location::TrustCertificate(fingerprint.clone()))
+                        },
+                        Some(ConnectionState::CertificateMismatch { expected, presented }) => {
+                            Some(Location::CertificateMismatch { expected: expected.clone(), presented: presented.clone() })
+                        },
+                        _ => None,
+                    };
+                    if let Some(location) = location {
+                        // Only ask once, the connection is closed anyway
+                        *server_state = Some(ConnectionState::Disconnected);
+                        self.location = location;
+                    }
+                },
+                Location::ConfigureServer | Location::TrustCertificate(_) | Location::CertificateMismatch { .. } => {
+                    if !self.events_splash(&events) {
+                        break;
+                    }
+                },
+                Location::Lobby => {
+                    let current_room_id = self.client.as_ref().unwrap().lock().unwrap().current_room_id;
+                    if !self.events_lobby(&events) {
+                        break;
+                    }
+                    if current_room_id != Some(0) && current_room_id.is_some() {
+                        self.location = Location::Room;
+                        self.selected_item = Some(0);
+                        continue;
+                    }
+                },
+                Location::Room => {
+                    let in_game = {
+                        let client = self.client.as_ref().unwrap().lock().unwrap();
+                        client.linked_id.is_some() && client.map.is_some()
+                    };
+                    if in_game {
+                        self.location = Location::Game;
+                        continue;
+                    }
+                    let current_room_id = self.client.as_ref().unwrap().lock().unwrap().current_room_id;
+                    if current_room_id == Some(0) {
+                        self.room_to_join = String::new();
+                        self.location = Location::Lobby;
+                        continue;
+                    }
+                    if !self.events_in_room(&events) {
+                        break;
+                    }
+                },
+                Location::Game => {
+                    if !self.events_in_game(&events) {
+                        break;
+                    }
+                    let over = {
+                        let client = self.client.as_ref().unwrap().lock().unwrap();
+                        client.round.as_ref().is_some_and(|round| round.result.is_some())
+                    };
+                    if over {
+                        self.location = Location::Results;
+                    }
+                },
+                Location::Results => {
+                    if !self.events_results(&events) {
+                        break;
+                    }
+                },
+            }
+        }
+
+        if self.key_releases {
+            disable_key_releases(terminal.backend_mut())?;
+        }
+        Ok(())
+    }
+
+    fn render_splash<B: tui::backend::Backend>(&mut self, f: &mut Frame<B>) {
+        // TODO paragraph
+        let size = f.size();
+        
+        let logo_odd = vec!["                                                                    ",
+"                                                                                                ",
+"                >\"<                                                                            ",
+"                 █╗                                                                             ",
+"                █╔╝                                                                             ",
+"   ██████╗  ██████╗ ███╗   ███╗██████╗ ███████╗██████╗ ██╗   ██╗███████╗████████╗               ",
+"   ██╔══██╗██╔═══██╗████╗ ████║██╔══██╗██╔════╝██╔══██╗██║   ██║██╔════╝╚══██╔══╝               ",
+"   ██████╔╝██║   ██║██╔████╔██║██████╔╝█████╗  ██████╔╝██║   ██║███████╗   ██║                  ",
+"   ██╔══██╗██║   ██║██║╚██╔╝██║██╔══██╗██╔══╝  ██╔══██╗██║   ██║╚════██║   ██║                  ",
+"   ██████╔╝╚██████╔╝██║ ╚═╝ ██║██████╔╝███████╗██║  ██║╚██████╔╝███████║   ██║       ███████╗   ",
+"   ╚═════╝  ╚═════╝ ╚═╝     ╚═╝╚═════╝ ╚══════╝╚═╝  ╚═╝ ╚═════╝ ╚══════╝   ╚═╝       ╚══════╝   ",
+"                                                        ___   ___     ___                       ",
+"                                                    _ _|   | |_  |   |_  |                      ",
+"                                                   | | | | |_ _| |_ _ _| |_                     ",
+"                                                    \\_/|___|_|_____|_|_____|                   "];
+        
+        let logo = vec!["                                                            ",
+"                                                                                    ",
+"                -\"-                                                                ",
+"                 █╗                                                                 ",
+"                █╔╝                                                                 ",
+"   ██████╗  ██████╗ ███╗   ███╗██████╗ ███████╗██████╗ ██╗   ██╗███████╗████████╗   ",
+"   ██╔══██╗██╔═══██╗████╗ ████║██╔══██╗██╔════╝██╔══██╗██║   ██║██╔════╝╚══██╔══╝   ",
+"   ██████╔╝██║   ██║██╔████╔██║██████╔╝█████╗  ██████╔╝██║   ██║███████╗   ██║      ",
+"   ██╔══██╗██║   ██║██║╚██╔╝██║██╔══██╗██╔══╝  ██╔══██╗██║   ██║╚════██║   ██║      ",
+"   ██████╔╝╚██████╔╝██║ ╚═╝ ██║██████╔╝███████╗██║  ██║╚██████╔╝███████║   ██║      ",
+"   ╚═════╝  ╚═════╝ ╚═╝     ╚═╝╚═════╝ ╚══════╝╚═╝  ╚═╝ ╚═════╝ ╚══════╝   ╚═╝      ",
+"                                                        ___   ___     ___           ",
+"                                                    _ _|   | |_  |   |_  |          ",
+"                                                   | | | | |_ _| |_ _ _| |_         ",
+"                                                    \\_/|___|_|_____|_|_____|       "];
+        Canvas::default()
+            .block(Block::default().borders(Borders::NONE))
+            .paint(|ctx| {
+                let mut idx = 3;
+                let line_len = logo_odd[logo_odd.len() - 1].len();
+                let offset_x = (size.width - line_len as u16) / 2;
+                let now = SystemTime::now();
+                let odd_sec = now.duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs().is_multiple_of(2);
+                if odd_sec {
+                    for line in &logo_odd {
+                        ctx.print(offset_x as f64, ((size.height / 2) - idx) as f64, line, Color::Yellow);
+                        idx += 1;
+                    }
+                } else {
+                    for line in &logo {
+                        ctx.print(offset_x as f64, ((size.height / 2) - idx) as f64, line, Color::Yellow);
+                        idx += 1;
+                    }
+                }
+            })
+            .x_bounds([0.0, size.width as f64])
+            .y_bounds([0.0, (size.height / 2) as f64])
+            .render(f, Rect::new(0, 0, size.width, size.height / 2));            
+    }
+
+
+    fn render_game<B: tui::backend::Backend>(&mut self, f: &mut Frame<B>) {
+        let size = f.size();
+
+        let title = format!("Bomberust - {}", self.latency());
+        Canvas::default()
+            .block(Block::default().borders(Borders::ALL).title(&title))
+            .paint(|_ctx| {})
+            .render(f, Rect::new(0, 0, size.width, size.height));  
+
+        let (client_map, layout, glyphs, positions, hud, died_at, bombs, flames, danger) = {
+            let client = self.client.as_ref().unwrap().lock().unwrap();
+            let map = client.map.as_ref().unwrap().clone();
+            let layout = GameLayout::new(size, map.w, map.h);
+            // The players are shown the same way on the map and in the side panel
+            let glyphs = match layout {
+                Some(layout) => self.theme.glyphs(layout.cell_w),
+                None => &self.theme.glyphs,
+            };
+            let now = Instant::now();
+            let positions: Vec<(f32, f32)> = (0..map.players.len())
+                .map(|id| client.player_position(id, now).unwrap_or_default())
+                .collect();
+            let names = TuiClient::player_names(&client);
+            let hud = player_lines(&map, &names, glyphs, &client.effects, client.linked_id, now);
+            let died_at = match (&client.round, client.linked_id) {
+                (Some(round), Some(id)) => round.died_at(id),
+                _ => None,
+            };
+            let flames: Vec<Flame> = client.flames.iter().filter(|flame| flame.until > now).cloned().collect();
+            let danger = if self.config.danger_zone {
+                danger_zone(&map, &client.bombs)
+            } else {
+                Vec::new()
+            };
+            (map, layout, glyphs, positions, hud, died_at, client.bombs.clone(), flames, danger)
+        };
+        let layout = match layout {
+            Some(layout) => layout,
+            None => {
+                TuiClient::draw_too_small(f, size, GameLayout::min_size(client_map.w, client_map.h));
+                return;
+            }
+        };
+        let game_width = match layout.hud {
+            Some(rect) => {
+                self.draw_hud(f, rect, &hud);
+                rect.x
+            },
+            None => size.width,
+        };
+        MapWidget::new(&client_map, glyphs)
+            .cell_size(layout.cell_w, layout.cell_h)
+            .positions(&positions)
+            .bombs(&bombs)
+            .flames(&flames)
+            .danger(&danger)
+            .render(f, layout.map);
+
+        if let Some(died_at) = died_at {
+            if died_at.elapsed() < DEATH_OVERLAY_TIME {
+                TuiClient::draw_you_died(f, Rect::new(0, 0, game_width, size.height));
+            } else {
+                TuiClient::draw_spectating(f, Rect::new(0, 0, game_width, size.height));
+            }
+        }
+    }
+
+    fn player_names(client: &Client) -> Vec<String> {
+        let mut names = client.player_names.clone();
+        let players = client.map.as_ref().map_or(0, |map| map.players.len());
+        let id = client.linked_id.and_then(|id| usize::try_from(id).ok()).filter(|id| *id < players);
+        if let (Some(id), Some(name)) = (id, &client.player_name) {
+            // The server may not send the names, but we know ours
+            if names.len() <= id {
+                names.resize(id + 1, String::new());
+            }
+            if names[id].is_empty() {
+                names[id] = name.clone();
+            }
+        }
+        names
+    }
+
+    fn draw_you_died<B: tui::backend::Backend>(f: &mut Frame<B>, area: Rect) {
+        if area.width < 20 || area.height < 5 {
+            return;
+        }
+        let style = Style::default().fg(Color::White).bg(Color::Red).modifier(Modifier::BOLD);
+        let text = [Text::styled("\nYOU DIED", style)];
+        Paragraph::new(text.iter())
+            .alignment(Alignment::Center)
+            .style(style)
+            .block(Block::default().borders(Borders::ALL).border_style(style))
+            .render(f, Rect::new(area.x + (area.width - 20) / 2, area.y + (area.height - 5) / 2, 20, 5));
+    }
+
+    fn draw_spectating<B: tui::backend::Backend>(f: &mut Frame<B>, area: Rect) {
+        if area.height < 3 {
+            return;
+        }
+        let text = [
+            Text::styled("Spectating until the end of the game", Style::default().fg(Color::Gray).modifier(Modifier::ITALIC)),
+        ];
+        Paragraph::new(text.iter())
+            .alignment(Alignment::Center)
+            .render(f, Rect::new(area.x + 1, area.y + area.height - 2, area.width.saturating_sub(2), 1));
+    }
+
+    fn draw_too_small<B: tui::backend::Backend>(f: &mut Frame<B>, area: Rect, (min_w, min_h): (usize, usize)) {
+        let text = [
+            Text::styled("Terminal too small\n\n", Style::default().fg(Color::Red).modifier(Modifier::BOLD)),
+            Text::raw(format!("{}x{} needed, {}x{} available", min_w, min_h, area.width, area.height)),
+        ];
+        Paragraph::new(text.iter())
+            .wrap(true)
+            .alignment(Alignment::Center)
+            .render(f, Rect::new(area.x, area.y + area.height / 3, area.width, area.height - area.height / 3));
+    }
+
+    fn draw_results<B: tui::backend::Backend>(&mut self, f: &mut Frame<B>) {
+        let size = f.size();
+        let lines = {
+            let client = self.client.as_ref().unwrap().lock().unwrap();
+            let result = client.round.as_ref().and_then(|round| round.result.clone());
+            match result {
+                Some(result) => result_lines(&result, &TuiClient::player_names(&client), &self.theme.glyphs, client.linked_id),
+                None => Vec::new(),
+            }
+        };
+        let style = Style::default().fg(Color::Yellow);
+        Paragraph::new(lines.iter())
+            .wrap(true)
+            .style(Style::default().fg(Color::White))
+            .block(Block::default().borders(Borders::ALL).title("Results").title_style(style).border_style(style))
+            .render(f, Rect::new(size.width / 4, size.height / 2, size.width / 2, size.height / 2));
+    }
+
+    fn draw_hud<B: tui::backend::Backend>(&self, f: &mut Frame<B>, rect: Rect, lines: &[Text]) {
+        Paragraph::new(lines.iter())
+            .wrap(true)
+            .block(Block::default().borders(Borders::LEFT).title("Players"))
+            .render(f, rect);
+    }
+
+    fn draw_servers_list<B: tui::backend::Backend>(&mut self, f: &mut Frame<B>) {
+        let size = f.size();
+
+        let mut servers_list = vec!["Add a new server to the list"];
+        for serv in &self.config.servers {
+            servers_list.push(&*serv.address);
+        }
+        self.items_len = servers_list.len();
+
+        let state = self.server_state.lock().unwrap().clone();
+        let mut list_height = size.height / 2;
+        if let Some(ConnectionState::Incompatible(ref reason)) = state {
+            let message_height = std::cmp::min(5, list_height / 2);
+            list_height -= message_height;
+            self.draw_incompatible(f, reason, Rect::new(0, size.height / 2 + list_height, size.width, message_height));
+        }
+        let title = match (&self.connected_item, state) {
+            (Some(s), Some(ConnectionState::Connected)) => format!("Servers - {} (Connected, {})", s, self.latency()),
+            (Some(s), Some(ConnectionState::Incompatible(_))) => format!("Servers - {} (Incompatible)", s),
+            (Some(s), Some(state)) => format!("Servers - {} ({})", s, state),
+            _ => String::from("Servers")
+        };
+
+        // TODO title style when connecting
+        SelectableList::default()
+                .block(Block::default().borders(Borders::ALL).title(&title))
+                .items(&servers_list)
+                .select(self.selected_item)
+                .highlight_style(Style::default().fg(Color::LightGreen).modifier(Modifier::BOLD))
+                .highlight_symbol(">")
+                .render(f, Rect::new(0, size.height / 2, size.width, list_height));
+    }
+
+    fn draw_incompatible<B: tui::backend::Backend>(&self, f: &mut Frame<B>, reason: &str, rect: Rect) {
+        let server = self.connected_item.clone().unwrap_or_default();
+        let style = Style::default().fg(Color::Red).modifier(Modifier::BOLD);
+        let message = format!("Can't play on {}: {}.\n", server, reason);
+        let text = [
+            Text::styled(&message, style),
+            Text::raw("Please update the client or choose another server."),
+        ];
+        Paragraph::new(text.iter())
+            .wrap(true)
+            .style(Style::default().fg(Color::White))
+            .block(Block::default().borders(Borders::ALL).title("Incompatible server").title_style(style).border_style(style))
+            .render(f, rect);
+    }
+
+    fn draw_room_view<B: tui::backend::Backend>(&mut self, f: &mut Frame<B>) {
+        let size = f.size();
+
+        let now = SystemTime::now();
+        let odd_sec = now.duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs().is_multiple_of(2);
+        let color = if odd_sec { Color::Yellow } else { Color::Rgb(225, 125, 0) };
+        let style = Style::default().fg(color);
+
+        Canvas::default()
+            .block(
+                Block::default()
+                .borders(Borders::ALL).title("Room configuration")
+                .border_style(style).title_style(style)
+            ).paint(|ctx| {
+                // TODO
+                ctx.print(0.0, (size.height / 4) as f64, "=> Not available for now", Color::Yellow);
+                ctx.print((size.width / 6) as f64 - 1.5, 1.0, "GO!", Color::White);
+            })
+            .x_bounds([0.0, (size.width / 3) as f64])
+            .y_bounds([0.0, (size.height / 2) as f64])
+            .render(f, Rect::new(0, size.height / 2, size.width / 3, size.height / 2));
+
+        Canvas::default()
+            .paint(|_ctx| {})
+            .block(Block::default().borders(Borders::NONE).style(Style::default().bg(color)))
+            .render(f, Rect::new(1, size.height - 4, size.width / 3 - 2, 3));
+
+        Canvas::default()
+            .block(Block::default().borders(Borders::ALL).title("Players"))
+            .paint(|ctx| {
+                ctx.print(0.0, (size.height / 4) as f64, "=> Not available for now", Color::Yellow);
+                // TODO
+            })
+            .x_bounds([0.0, size.width as f64])
+            .y_bounds([0.0, (size.height / 2) as f64])
+            .render(f, Rect::new(size.width / 3, size.height / 2, size.width / 3, size.height / 2));
+
+        Canvas::default()
+            .block(Block::default().borders(Borders::ALL).title("Chat"))
+            .paint(|ctx| {
+                ctx.print(0.0, (size.height / 4) as f64, "=> Not available for now", Color::Yellow);
+                // TODO
+            })
+            .x_bounds([0.0, size.width as f64])
+            .y_bounds([0.0, (size.height / 2) as f64])
+            .render(f, Rect::new(2 * size.width / 3, size.height / 2, size.width / 3, size.height / 2));
+    }
+
+    fn draw_rooms_list<B: tui::backend::Backend>(&mut self, f: &mut Frame<B>) {
+        let size = f.size();
+
+        let new_room = format!("{} Create a new room\n", if self.selected_item == Some(0) { ">" } else { "-" });
+        let join_room = format!("{} Join a room (type ID and press Enter): {}\n", if self.selected_item == Some(1) { ">" } else { "-" }, self.room_to_join);
+
+        let rooms_list = [
+            Text::styled(&new_room, if self.selected_item == Some(0) { Style::default().fg(Color::LightGreen).modifier(Modifier::BOLD) } else { Style::default() }),
+            Text::styled(&join_room, if self.selected_item == Some(1) { Style::default().fg(Color::LightGreen).modifier(Modifier::BOLD) } else { Style::default() }),
+        ];
+        self.items_len = rooms_list.len();
+
+        Paragraph::new(rooms_list.iter())
+            .wrap(true)
+            .style(Style::default().fg(Color::White))
+            .block(Block::default().borders(Borders::ALL).title("Rooms"))
+            .render(f, Rect::new(0, size.height / 2, size.width, size.height / 2));
+    }
+
+    
+
+    fn configure_new_server<B: tui::backend::Backend>(&mut self, f: &mut Frame<B>) {
+        let size = f.size();
+
+        let name = format!("Player name: {}\n", self.new_server_info.as_ref().unwrap().name);
+        let address = format!("Address:     {}\n", self.new_server_info.as_ref().unwrap().address);
+        let server_name = format!("Server name: {}\n", self.new_server_info.as_ref().unwrap().server_name);
+        let certificate = format!("Certificate: {}\n", self.new_server_info.as_ref().unwrap().certificate);
+        let client_certificate = format!("Client cert: {}\n", self.new_server_info.as_ref().unwrap().client_certificate);
+        let client_key = format!("Client key:  {}\n", self.new_server_info.as_ref().unwrap().client_key);
+        let trust_on_first_use = format!("Trust on first use: [{}] (Space to toggle)\n",
+            if self.new_server_info.as_ref().unwrap().trust_on_first_use { "x" } else { " " });
+        let error = format!("\n{}\n", self.last_error);
+
+        let playing_text = vec![
+            Text::styled(&name, if self.selected_item == Some(0) { Style::default().fg(Color::LightGreen).modifier(Modifier::BOLD) } else { Style::default() }),
+            Text::styled(&address, if self.selected_item == Some(1) { Style::default().fg(Color::LightGreen).modifier(Modifier::BOLD) } else { Style::default() }),
+            Text::styled(&server_name, if self.selected_item == Some(2) { Style::default().fg(Color::LightGreen).modifier(Modifier::BOLD) } else { Style::default() }),
+            Text::styled(&certificate, if self.selected_item == Some(3) { Style::default().fg(Color::LightGreen).modifier(Modifier::BOLD) } else { Style::default() }),
+            Text::styled(&client_certificate, if self.selected_item == Some(4) { Style::default().fg(Color::LightGreen).modifier(Modifier::BOLD) } else { Style::default() }),
+            Text::styled(&client_key, if self.selected_item == Some(5) { Style::default().fg(Color::LightGreen).modifier(Modifier::BOLD) } else { Style::default() }),
+            Text::styled(&trust_on_first_use, if self.selected_item == Some(6) { Style::default().fg(Color::LightGreen).modifier(Modifier::BOLD) } else { Style::default() }),
+            Text::styled("Save", if self.selected_item == Some(7) { Style::default().fg(Color::LightGreen).modifier(Modifier::BOLD) } else { Style::default() }),
+            Text::styled(&error, Style::default().fg(Color::Red).modifier(Modifier::BOLD)),
+        ];
+        self.items_len = playing_text.len() - 1;
+
+        Paragraph::new(playing_text.iter())
+            .wrap(true)
+            .style(Style::default().fg(Color::White))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Configure")
+            )
+            .render(f, Rect::new(0, size.height / 2, size.width, size.height / 2));
+    }
+
+    fn latency(&self) -> String {
+        match self.client {
+            Some(ref client) => client.lock().unwrap().heartbeat.to_string(),
+            None => String::from("RTT -"),
+        }
+    }
+
+    fn in_session(&self) -> bool {
+        matches!(self.location, Location::Lobby | Location::Room | Location::Game | Location::Results)
+    }
+
+    fn draw_reconnecting<B: tui::backend::Backend>(f: &mut Frame<B>, attempt: u32) {
+        let size = f.size();
+        if size.height < 3 {
+            return;
+        }
+        let text = [
+            Text::styled(format!("Connection lost. Reconnecting (attempt {})...", attempt),
+                         Style::default().fg(Color::Yellow).modifier(Modifier::BOLD)),
+        ];
+        Paragraph::new(text.iter())
+            .block(Block::default().borders(Borders::ALL))
+            .render(f, Rect::new(0, 0, size.width, 3));
+    }
+
+    fn draw_trust_certificate<B: tui::backend::Backend>(&mut self, f: &mut Frame<B>) {
+        let size = f.size();
+
+        let fingerprint = match self.location {
+            Location::TrustCertificate(ref fingerprint) => fingerprint.clone(),
+            _ => String::new(),
+        };
+        let server = self.connected_item.clone().unwrap_or_default();
+        let intro = format!("{} presented a certificate which is not trusted yet.\n\nSHA-256 fingerprint:\n", server);
+        let fingerprint = format!("{}\n\n", fingerprint);
+        let text = [
+            Text::raw(&intro),
+            Text::styled(&fingerprint, Style::default().fg(Color::Yellow).modifier(Modifier::BOLD)),
+            Text::raw("Check this fingerprint with the server administrator before trusting it.\n\n"),
+            Text::styled("Enter: trust and connect    Esc: cancel", Style::default().fg(Color::LightGreen)),
+        ];
+
+        Paragraph::new(text.iter())
+            .wrap(true)
+            .style(Style::default().fg(Color::White))
+            .block(Block::default().borders(Borders::ALL).title("Trust on first use"))
+            .render(f, Rect::new(0, size.height / 2, size.width, size.height / 2));
+    }
+
+    fn draw_certificate_mismatch<B: tui::backend::Backend>(&mut self, f: &mut Frame<B>) {
+        let size = f.size();
+
+        let (expected, presented) = match self.location {
+            Location::CertificateMismatch { ref expected, ref presented } => (expected.clone(), presented.clone()),
+            _ => (String::new(), String::new()),
+        };
+        let server = self.connected_item.clone().unwrap_or_default();
+        let intro = format!("The certificate of {} does not match the pinned fingerprint.\nSomeone may be intercepting the connection, so it was rejected.\n\n", server);
+        let expected = format!("Pinned:    {}\n", expected);
+        let presented = format!("Presented: {}\n\n", presented);
+        let style = Style::default().fg(Color::Red).modifier(Modifier::BOLD);
+        let text = [
+            Text::styled(&intro, style),
+            Text::raw(&expected),
+            Text::raw(&presented),
+            Text::raw("If the certificate was legitimately changed, remove and add the server again.\n\n"),
+            Text::styled("Esc: back", Style::default().fg(Color::LightGreen)),
+        ];
+
+        Paragraph::new(text.iter())
+            .wrap(true)
+            .style(Style::default().fg(Color::White))
+            .block(Block::default().borders(Borders::ALL).title("WARNING: certificate changed").title_style(style).border_style(style))
+            .render(f, Rect::new(0, size.height / 2, size.width, size.height / 2));
+    }
+
+    /**
+     * Pin the fingerprint for the server we tried to connect to and connect again
+     * @param fingerprint   the fingerprint confirmed by the user
+     */
+    fn trust_certificate(&mut self, fingerprint: String) {
+        self.selected_item = Some(0);
+        self.location = Location::Splash;
+        let address = self.connected_item.clone().unwrap_or_default();
+        let server_idx = self.config.servers.iter().position(|s| s.address == address);
+        if let Some(server_idx) = server_idx {
+            self.config.servers[server_idx].fingerprint = fingerprint;
+            if let Err(e) = self.save_servers() {
+                error!("Can't save config.json: {}", e);
+            }
+            self.connect_server(server_idx);
+        }
+    }
+
+    fn save_servers(&mut self) -> std::io::Result<()> {
+        let content = serde_json::to_string(&self.config)?;
+        let mut file = File::create("config.json")?;
+        file.write_all(content.as_bytes())?;
+        Ok(())
+    }
+
+    fn connect_server(&mut self, server_idx: usize) {
+        if self.config.servers.len() < server_idx || self.client_thread.is_some() {
+            match *self.server_state.lock().unwrap() {
+                Some(ref state) if state.is_closed() => {},
+                _ => return,
+            }
+        }
+
+        let server = self.config.servers.get(server_idx).unwrap().clone();
+        let heartbeat = HeartbeatPolicy {
+            max_missed: self.config.max_missed_pongs,
+            ..Default::default()
+        };
+        let (tx, rx) = mpsc::channel::<Vec<u8>>(OUTBOUND_QUEUE_SIZE);
+        *self.server_state.lock().unwrap() = Some(ConnectionState::Connecting);
+        let server_state = self.server_state.clone();
+        self.connected_item = Some(server.address.clone());
+
+        let mut client = Client::new(tx);
+        client.player_name = Some(server.name.clone());
+        let client = Arc::new(Mutex::new(client));
+        let client_cloned = client.clone();
+        self.client = Some(client);
+        self.client_thread = Some(thread::spawn(move || {
+            let config = TlsClientConfig {
+                server_state,
+                addr: server.address.clone(),
+                server_name: server.server_name.clone(),
+                cert: server.certificate.clone(),
+                trust_on_first_use: server.trust_on_first_use,
+                fingerprint: server.fingerprint.clone(),
+                client_cert: server.client_certificate.clone(),
+                client_key: server.client_key.clone(),
+                reconnect: ReconnectPolicy::default(),
+                heartbeat,
+                handshake_timeout: HANDSHAKE_TIMEOUT,
+                client: client_cloned,
+            };
+            TlsClient::start(&config, rx);
+        }));
+    }
+
+    // Keys ignored on a screen must not fall through to the other arms, like Char(c)
+    #[allow(clippy::collapsible_match)]
+    fn events_splash(&mut self, events: &Events) -> bool {
+        // TODO split in functions
+        let events = events.next_before(self.next_frame);
+        if events.is_err() {
+            return true;
+        }
+        match events.unwrap() {
+            Event::Input(input) => match input {
+                Key::Esc => {
+                    if self.location == Location::Splash {
+                        return false;
+                    } else {
+                        self.selected_item = Some(0);
+                        self.location = Location::Splash;
+                    }
+                },
+                Key::Down => {
+                    self.selected_item = if let Some(selected) = self.selected_item {
+                        if selected >= self.items_len - 1 {
+                            Some(0)
+                        } else {
+                            Some(selected + 1)
+                        }
+                    } else {
+                        Some(0)
+                    };
+                },
+                Key::Up => {
+                    self.selected_item = if let Some(selected) = self.selected_item {
+                        if selected > 0 {
+                            Some(selected - 1)
+                        } else {
+                            Some(self.items_len - 1)
+                        }
+                    } else {
+                        Some(0)
+                    };
+                },
+                Key::Char('\n') => {
+                    if self.location == Location::Splash {
+                        let selected = self.selected_item.unwrap_or(0);
+                        if selected == 0 {
+                            self.new_server_info = Some(ServerInfo {
+                                name: self.config.default_playername.clone(),
+                                certificate: String::new(),
+                                server_name: String::new(),
+                                address: String::new(),
+                                client_certificate: String::new(),
+                                client_key: String::new(),
+                                trust_on_first_use: false,
+                                fingerprint: String::new(),
+                            });
+                            self.last_error = String::new();
+                            self.location = Location::ConfigureServer;
+                        } else {
+                            self.connect_server(selected - 1);
+                        }
+                    } else if let Location::TrustCertificate(fingerprint) = &self.location {
+                        let fingerprint = fingerprint.clone();
+                        self.trust_certificate(fingerprint);
+                    } else if self.location == Location::ConfigureServer && self.selected_item == Some(7) {
+                        let new_server = self.new_server_info.clone().unwrap();
+                        if new_server.name.is_empty() {
+                            self.last_error = String::from("Please enter your player name");
+                            return true;
+                        }
+                        let address = ServerAddress::parse(&new_server.address)
+                            .and_then(|addr| addr.server_name(&new_server.server_name));
+                        if let Err(e) = address {
+                            self.last_error = e.to_string();
+                            return true;
+                        }
+                        if new_server.client_certificate.is_empty() != new_server.client_key.is_empty() {
+                            self.last_error = String::from("Please provide both the client certificate and its key");
+                            return true;
+                        }
+                        if self.config.servers.is_empty() {
+                            self.config.default_playername = new_server.name.clone();
+                        }
+
+                        let mut add_server = true;
+                        for serv in &self.config.servers {
+                            if serv.address == new_server.address {
+                                add_server = false;
+                                break;
+                            }
+                        }
+                        if add_server {
+                            self.config.servers.push(new_server);
+                            if let Err(e) = self.save_servers() {
+                                warn!("Can't save the servers: {}", e);
+                            }
+                        }
+                        self.new_server_info = None;
+                        self.selected_item = Some(0);
+                        self.location = Location::Splash;
+                    } else if self.location == Location::ConfigureServer {
+                        self.selected_item = if let Some(selected) = self.selected_item {
+                            if selected >= self.items_len - 1 {
+                                Some(0)
+                            } else {
+                                Some(selected + 1)
+                            }
+                        } else {
+                            Some(0)
+                        };
+                    }
+                },
+                Key::Char('\t') => {
+                    if self.location == Location::ConfigureServer {
+                        self.selected_item = if let Some(selected) = self.selected_item {
+                            if selected >= self.items_len - 1 {
+                                Some(0)
+                            } else {
+                                Some(selected + 1)
+                            }
+                        } else {
+                            Some(0)
+                        };
+                    }
+                }
+                Key::Char(c) => {
+                    if self.location == Location::ConfigureServer {
+                        match self.selected_item {
+                            Some(0) => self.new_server_info.as_mut().unwrap().name.push(c),
+                            Some(1) => self.new_server_info.as_mut().unwrap().address.push(c),
+                            Some(2) => self.new_server_info.as_mut().unwrap().server_name.push(c),
+                            Some(3) => self.new_server_info.as_mut().unwrap().certificate.push(c),
+                            Some(4) => self.new_server_info.as_mut().unwrap().client_certificate.push(c),
+                            Some(5) => self.new_server_info.as_mut().unwrap().client_key.push(c),
+                            Some(6) => if c == ' ' {
+                                let server_info = self.new_server_info.as_mut().unwrap();
+                                server_info.trust_on_first_use = !server_info.trust_on_first_use;
+                            },
+                            _ => {}
+                        }
+                    }
+                },
+                Key::Backspace => {
+                    if self.location == Location::ConfigureServer {
+                        match self.selected_item {
+                            Some(0) => { self.new_server_info.as_mut().unwrap().name.pop(); },
+                            Some(1) => { self.new_server_info.as_mut().unwrap().address.pop(); },
+                            Some(2) => { self.new_server_info.as_mut().unwrap().server_name.pop(); },
+                            Some(3) => { self.new_server_info.as_mut().unwrap().certificate.pop(); },
+                            Some(4) => { self.new_server_info.as_mut().unwrap().client_certificate.pop(); },
+                            Some(5) => { self.new_server_info.as_mut().unwrap().client_key.pop(); },
+                            _ => {}
+                        }
+                    }
+                },
+                Key::Delete => {
+                    let selection = self.selected_item.unwrap_or(0);
+                    if self.location == Location::Splash && selection > 0 {
+                        self.config.servers.remove(selection - 1);
+                        self.selected_item = Some(0);
+                        if let Err(e) = self.save_servers() {
+                            warn!("Can't save the servers: {}", e);
+                        }
+                    }
+                }
+                _ => {}
+            },
+            Event::Release(_) | Event::Tick => {
+            }
+        }
+        true
+    }
+
+    // Keys ignored on a screen must not fall through to the other arms, like Char(c)
+    #[allow(clippy::collapsible_match)]
+    fn events_lobby(&mut self, events: &Events) -> bool {
+        let events = events.next_before(self.next_frame);
+        if events.is_err() {
+            return true;
+        }
+        match events.unwrap() {
+            Event::Input(input) => match input {
+                Key::Esc => {
+                    return false;
+                    //self.selected_item = Some(0);
+                    //self.location = Location::Splash;
+                    // TODO return to splash + close connection
+                },
+                Key::Down => {
+                    self.selected_item = if let Some(selected) = self.selected_item {
+                        if selected >= self.items_len - 1 {
+                            Some(0)
+                        } else {
+                            Some(selected + 1)
+                        }
+                    } else {
+                        Some(0)
+                    };
+                },
+                Key::Up => {
+                    self.selected_item = if let Some(selected) = self.selected_item {
+                        if selected > 0 {
+                            Some(selected - 1)
+                        } else {
+                            Some(self.items_len - 1)
+                        }
+                    } else {
+                        Some(0)
+                    };
+                },
+                Key::Char('\n') => {
+                    if self.selected_item == Some(0) {
+                        self.send(ClientCommand::Create);
+                    } else if self.selected_item == Some(1) {
+                        let room: u64 = self.room_to_join.parse().unwrap_or(0);
+                        self.send(ClientCommand::Join { room });
+                    }
+                },
+                Key::Char('\t') => {
+                    if self.location == Location::ConfigureServer {
+                        self.selected_item = if let Some(selected) = self.selected_item {
+                            if selected >= self.items_len - 1 {
+                                Some(0)
+                            } else {
+                                Some(selected + 1)
+                            }
+                        } else {
+                            Some(0)
+                        };
+                    }
+                },
+                Key::Char(c) => {
+                    self.room_to_join.push(c);
+                },
+                Key::Backspace => {
+                    self.room_to_join.pop();
+                },
+                _ => {}
+            },
+            Event::Release(_) | Event::Tick => {
+            }
+        }
+        true
+    }
+
+    fn events_in_room(&mut self, events: &Events) -> bool {
+        let events = events.next_before(self.next_frame);
+        if events.is_err() {
+            return true;
+        }
+        match events.unwrap() {
+            Event::Input(input) => match input {
+                Key::Esc => {
+                    self.send(ClientCommand::Leave);
+                },
+                Key::Char('\n') if self.selected_item == Some(0) => {
+                    self.send(ClientCommand::Launch);
+                },
+                _ => {}
+            },
+            Event::Release(_) | Event::Tick => {
+            }
+        }
+        true
+    }
+
+    fn events_in_game(&mut self, events: &Events) -> bool {
+        // Wake up for the next move of a held direction
+        let deadline = match self.held_move.deadline() {
+            Some(next_move) => std::cmp::min(next_move, self.next_frame),
+            None => self.next_frame,
+        };
+        if let Ok(event) = events.next_before(deadline) {
+            match event {
+                Event::Input(input) => match input {
+                    Key::Esc => {
+                        // TODO
+                    },
+                    _ if self.is_dead() => {
+                        // Spectating
+                    },
+                    Key::Char(' ') => {
+                        self.send(ClientCommand::Bomb);
+                    },
+                    Key::Char('z') => {
+                        self.config.danger_zone = !self.config.danger_zone;
+                    },
+                    key => {
+                        if let Some(direction) = TuiClient::key_direction(key) {
+                            if let Some(direction) = self.held_move.press(direction, Instant::now()) {
+                                self.move_to(direction);
+                            }
+                        }
+                    }
+                },
+                Event::Release(key) => {
+                    if let Some(direction) = TuiClient::key_direction(key) {
+                        self.held_move.release(direction, Instant::now());
+                    }
+                },
+                Event::Tick => {
+                }
+            }
+        }
+        if self.is_dead() {
+            self.held_move.stop();
+        }
+        if let Some(direction) = self.held_move.poll(Instant::now()) {
+            self.move_to(direction);
+        }
+        true
+    }
+
+    fn events_results(&mut self, events: &Events) -> bool {
+        let events = events.next_before(self.next_frame);
+        if events.is_err() {
+            return true;
+        }
+        match events.unwrap() {
+            Event::Input(input) => match input {
+                Key::Char('\n') => {
+                    self.close_results();
+                },
+                Key::Esc => {
+                    // The room is left when the server answers
+                    self.close_results();
+                    self.send(ClientCommand::Leave);
+                },
+                _ => {}
+            },
+            Event::Release(_) | Event::Tick => {
+            }
+        }
+        true
+    }
+
+    fn close_results(&mut self) {
+        if let Some(client) = &self.client {
+            let mut client = client.lock().unwrap();
+            // Keep the next game if it already started
+            if client.round.as_ref().is_none_or(|round| round.result.is_some()) {
+                client.leave_game();
+            }
+        }
+        self.selected_item = Some(0);
+        self.location = Location::Room;
+    }
+
+    fn is_dead(&self) -> bool {
+        match &self.client {
+            Some(client) => client.lock().unwrap().is_dead(),
+            None => false,
+        }
+    }
+
+    fn key_direction(key: Key) -> Option<Direction> {
+        match key {
+            Key::Char('w') => Some(Direction::North),
+            Key::Char('a') => Some(Direction::West),
+            Key::Char('s') => Some(Direction::South),
+            Key::Char('d') => Some(Direction::East),
+            _ => None,
+        }
+    }
+
+    fn move_to(&mut self, direction: Direction) {
+        if let Some(client) = &self.client {
+            if let Err(e) = client.lock().unwrap().move_to(direction) {
+                warn!("Can't move: {}", e);
+            }
+        }
+    }
+
+    fn send(&mut self, cmd: ClientCommand) {
+        if let Some(client) = &self.client {
+            if let Err(e) = client.lock().unwrap().send(cmd) {
+                warn!("Can't send command: {}", e);
+            }
+        }
+    }
+}
\ No newline at end of file
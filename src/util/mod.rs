@@ -4,13 +4,17 @@ use rand::rngs::ThreadRng;
 use std::io;
 use std::sync::mpsc;
 use std::thread;
-use std::time::Duration;
+use std::time::{ Duration, Instant };
 
-use termion::event::Key;
+use termion::event::{ self as input, Key };
 use termion::input::TermRead;
 
+use crate::ui::parse_key_event;
+
+#[derive(Debug, PartialEq)]
 pub enum Event<I> {
     Input(I),
+    Release(I), // Only reported by terminals supporting key releases, see ui::enable_key_releases
     Tick,
 }
 
@@ -48,17 +52,18 @@ impl Events {
             let tx = tx.clone();
             thread::spawn(move || {
                 let stdin = io::stdin();
-                for evt in stdin.keys() {
-                    match evt {
-                        Ok(key) => {
-                            if let Err(_) = tx.send(Event::Input(key)) {
-                                return;
-                            }
-                            if key == config.exit_key {
-                                return;
-                            }
-                        }
-                        Err(_) => {}
+                for evt in stdin.events() {
+                    let event = match evt {
+                        Ok(input::Event::Key(key)) => Event::Input(key),
+                        Ok(input::Event::Unsupported(seq)) => match parse_key_event(&seq) {
+                            Some(event) => event,
+                            None => continue,
+                        },
+                        _ => continue,
+                    };
+                    let exit = event == Event::Input(config.exit_key);
+                    if tx.send(event).is_err() || exit {
+                        return;
                     }
                 }
             })
@@ -83,6 +88,11 @@ impl Events {
     pub fn next(&self) -> Result<Event<Key>, mpsc::RecvError> {
         self.rx.recv()
     }
+
+    /// Wait for the next event until the deadline, to keep drawing frames
+    pub fn next_before(&self, deadline: Instant) -> Result<Event<Key>, mpsc::RecvTimeoutError> {
+        self.rx.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+    }
 }
 
 
@@ -142,7 +152,7 @@ pub struct TabsState<'a> {
 }
 
 impl<'a> TabsState<'a> {
-    pub fn new(titles: Vec<&'a str>) -> TabsState {
+    pub fn new(titles: Vec<&'a str>) -> TabsState<'a> {
         TabsState { titles, index: 0 }
     }
     pub fn next(&mut self) {
@@ -0,0 +1,227 @@
+/**
+ * Copyright (c) 2019, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+
+use bomberust_client::net::diff_msg::SerializedEvent;
+use bomberust_client::net::msg::{ ClientCommand, PongMsg, ServerMsg, WelcomeMsg, CAPABILITIES };
+use rmp_serde::Deserializer;
+use serde::Deserialize;
+use std::fs;
+use std::io::{ self, BufReader, Read, Write };
+use std::net::{ SocketAddr, TcpListener, TcpStream };
+use std::sync::Arc;
+use std::thread;
+use std::time::{ Duration, Instant };
+use tokio_rustls::rustls::{ NoClientAuth, ServerConfig, ServerSession, Session, StreamOwned };
+use tokio_rustls::rustls::internal::pemfile;
+
+/// CA which signed the certificate of the mock server (for localhost and 127.0.0.1)
+pub const CA_CERT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/ca.pem");
+const SERVER_CERT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/server.pem");
+const SERVER_KEY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/server.key");
+
+/// How long to wait for the client before failing the script
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/**
+ * What the mock server does next in a session
+ */
+#[derive(Debug)]
+pub enum Step {
+    /// Wait for the hello and answer with this protocol version and all the
+    /// capabilities of the client. Not recorded
+    Handshake(u32),
+    /// Same as Handshake, announcing only these capabilities
+    HandshakeWith(u32, Vec<&'static str>),
+    /// Write a message to the client
+    Send(ServerMsg),
+    /// Wait for this command. Pings are answered and skipped
+    Expect(ClientCommand),
+    /// Do nothing for a while
+    Sleep(Duration),
+    /// Read and drop everything, pings included, for a while
+    Ignore(Duration),
+    /// Close the connection and stop listening for a while (last step of a script)
+    Offline(Duration),
+}
+
+/**
+ * A game server on localhost speaking the real protocol (TLS, length prefixed msgpack)
+ * and replaying scripts. Each accepted connection plays the next script, then the
+ * connection is closed.
+ */
+pub struct MockServer {
+    pub addr: SocketAddr,
+    handle: thread::JoinHandle<Result<Vec<ClientCommand>, String>>,
+}
+
+type TlsStream = StreamOwned<ServerSession, TcpStream>;
+
+impl MockServer {
+    /**
+     * Listen on a random port and play the scripts in a new thread
+     * @param sessions  one script per connection to accept
+     * @return          the running server
+     */
+    pub fn start(sessions: Vec<Vec<Step>>) -> MockServer {
+        let mut listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let config = Arc::new(MockServer::tls_config());
+        let handle = thread::spawn(move || {
+            let mut received = Vec::new();
+            for script in sessions {
+                let socket = MockServer::accept(&listener).map_err(|e| format!("Accept failed: {}", e))?;
+                socket.set_read_timeout(Some(TIMEOUT)).unwrap();
+                let mut stream = StreamOwned::new(ServerSession::new(&config), socket);
+                let mut offline = None;
+                for step in script {
+                    match step {
+                        Step::Offline(duration) => offline = Some(duration),
+                        step => MockServer::play(&mut stream, step, &mut received)?,
+                    }
+                }
+                stream.sess.send_close_notify();
+                let _ = stream.flush();
+                if let Some(duration) = offline {
+                    drop(stream);
+                    drop(listener);
+                    thread::sleep(duration);
+                    listener = TcpListener::bind(addr).map_err(|e| format!("Can't listen again: {}", e))?;
+                }
+            }
+            Ok(received)
+        });
+        MockServer {
+            addr,
+            handle,
+        }
+    }
+
+    /**
+     * Wait for the end of all the scripts
+     * @return  every command received (without the pings), or what went wrong
+     */
+    pub fn join(self) -> Result<Vec<ClientCommand>, String> {
+        self.handle.join().map_err(|_| String::from("Mock server panicked"))?
+    }
+
+    fn tls_config() -> ServerConfig {
+        let certs = pemfile::certs(&mut BufReader::new(fs::File::open(SERVER_CERT).unwrap())).unwrap();
+        let mut keys = pemfile::pkcs8_private_keys(&mut BufReader::new(fs::File::open(SERVER_KEY).unwrap())).unwrap();
+        let mut config = ServerConfig::new(NoClientAuth::new());
+        config.set_single_cert(certs, keys.remove(0)).unwrap();
+        config
+    }
+
+    fn accept(listener: &TcpListener) -> io::Result<TcpStream> {
+        listener.set_nonblocking(true)?;
+        let deadline = Instant::now() + TIMEOUT;
+        loop {
+            match listener.accept() {
+                Ok((socket, _)) => {
+                    socket.set_nonblocking(false)?;
+                    return Ok(socket);
+                },
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock && Instant::now() < deadline => {
+                    thread::sleep(Duration::from_millis(10));
+                },
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn play(stream: &mut TlsStream, step: Step, received: &mut Vec<ClientCommand>) -> Result<(), String> {
+        match step {
+            Step::Handshake(version) => MockServer::play(stream, Step::HandshakeWith(version, CAPABILITIES.to_vec()), received),
+            Step::HandshakeWith(version, capabilities) => match MockServer::next_command(stream)? {
+                ClientCommand::Hello { .. } => {
+                    let capabilities = capabilities.iter().map(|c| c.to_string()).collect();
+                    let welcome = WelcomeMsg { version, capabilities };
+                    MockServer::write(stream, &ServerMsg::Welcome(welcome))
+                },
+                cmd => Err(format!("Expected a hello, got {:?}", cmd)),
+            },
+            Step::Send(msg) => MockServer::write(stream, &msg),
+            Step::Expect(expected) => {
+                let cmd = MockServer::next_command(stream)?;
+                received.push(cmd.clone());
+                if cmd != expected {
+                    return Err(format!("Expected {:?}, got {:?}", expected, cmd));
+                }
+                Ok(())
+            },
+            Step::Sleep(duration) => {
+                thread::sleep(duration);
+                Ok(())
+            },
+            Step::Ignore(duration) => {
+                let deadline = Instant::now() + duration;
+                stream.sock.set_read_timeout(Some(Duration::from_millis(50))).unwrap();
+                while Instant::now() < deadline {
+                    let mut buf = [0; 1024];
+                    match stream.read(&mut buf) {
+                        Ok(0) => break,
+                        Ok(_) => {},
+                        Err(ref e) if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut => {},
+                        Err(e) => return Err(e.to_string()),
+                    }
+                }
+                stream.sock.set_read_timeout(Some(TIMEOUT)).unwrap();
+                Ok(())
+            },
+            Step::Offline(_) => Err(String::from("Offline must be the last step of a script")),
+        }
+    }
+
+    /**
+     * Read the next command, answering the pings on the way
+     */
+    fn next_command(stream: &mut TlsStream) -> Result<ClientCommand, String> {
+        loop {
+            match MockServer::read(stream)? {
+                ClientCommand::Ping { id } => MockServer::write(stream, &ServerMsg::Pong(PongMsg { id }))?,
+                cmd => return Ok(cmd),
+            }
+        }
+    }
+
+    fn write(stream: &mut TlsStream, msg: &ServerMsg) -> Result<(), String> {
+        let pkt = msg.to_vec();
+        stream.write_all(&(pkt.len() as u16).to_be_bytes())
+            .and_then(|_| stream.write_all(&pkt))
+            .and_then(|_| stream.flush())
+            .map_err(|e| format!("Can't send {}: {}", msg.msg_type(), e))
+    }
+
+    fn read(stream: &mut TlsStream) -> Result<ClientCommand, String> {
+        let mut header = [0; 2];
+        stream.read_exact(&mut header).map_err(|e| format!("Can't read command: {}", e))?;
+        let mut pkt = vec![0; u16::from_be_bytes(header) as usize];
+        stream.read_exact(&mut pkt).map_err(|e| format!("Can't read command: {}", e))?;
+        let mut de = Deserializer::new(&pkt[..]);
+        Deserialize::deserialize(&mut de).map_err(|e| format!("Invalid command: {}", e))
+    }
+}
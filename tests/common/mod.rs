@@ -25,8 +25,6 @@
  * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  **/
 
-pub mod map;
-pub mod item;
-pub mod utils;
+// Helpers shared by the integration tests
 
-pub use map::Map;
\ No newline at end of file
+pub mod mockserver;
@@ -0,0 +1,200 @@
+/**
+ * Copyright (c) 2019, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+
+// Invalid packets from the server must be ignored without killing the client
+
+use bomberust_client::Client;
+use bomberust_client::fixtures::open_map;
+use bomberust_client::gen::item::{ Bonus, InteractiveItem };
+use bomberust_client::gen::map::{ Map, MAX_MAP_SIDE };
+use bomberust_client::net::diff_msg::*;
+use bomberust_client::net::msg::*;
+use futures::sync::mpsc;
+use proptest::prelude::*;
+
+fn client() -> Client {
+    let (tx, _) = mpsc::channel::<Vec<u8>>(1);
+    Client::new(tx)
+}
+
+fn in_game() -> Client {
+    let mut client = client();
+    let capabilities = CAPABILITIES.iter().map(|c| c.to_string()).collect();
+    client.parse_rtp(ServerMsg::Welcome(WelcomeMsg { version: PROTOCOL_VERSION, capabilities }).to_vec()).unwrap();
+    // Map::new is random and can take a while, proptest needs a lot of maps
+    client.parse_rtp(ServerMsg::Map(MapMsg { map: open_map(11, 11), names: Vec::new() }).to_vec()).unwrap();
+    client
+}
+
+fn framed(msgs: &[ServerMsg]) -> Vec<u8> {
+    let mut data = Vec::new();
+    for msg in msgs {
+        let pkt = msg.to_vec();
+        data.extend_from_slice(&(pkt.len() as u16).to_be_bytes());
+        data.extend_from_slice(&pkt);
+    }
+    data
+}
+
+#[test]
+fn diff_without_map_is_ignored() {
+    let mut client = client();
+    let msg = ServerMsg::BombExplode(BombExplode { w: 1, h: 1 });
+    assert_eq!(client.parse_rtp(msg.to_vec()), Err(MsgError::NoMap("bomb_explode")));
+    assert_eq!(client.protocol_errors, 1);
+}
+
+#[test]
+fn unknown_player_is_ignored() {
+    let mut client = in_game();
+    let moved = ServerMsg::PlayerMove(PlayerMove { id: -1, x: 1.0, y: 1.0, seq: None });
+    assert!(client.parse_rtp(moved.to_vec()).is_err());
+    let died = ServerMsg::PlayerDie(PlayerDie { id: 42 });
+    assert_eq!(client.parse_rtp(died.to_vec()), Err(MsgError::UnknownPlayer { msg_type: "player_die", id: 42 }));
+    let updated = ServerMsg::PlayerUpdate(PlayerUpdate { id: 4, radius: 2, speed_factor: 1000, bomb: 1, effects: Vec::new() });
+    assert!(client.parse_rtp(updated.to_vec()).is_err());
+    let identity = ServerMsg::PlayerIdentity(PlayerIdentity { id: u64::MAX });
+    assert_eq!(client.parse_rtp(identity.to_vec()), Err(MsgError::UnknownPlayer { msg_type: "player_identity", id: -1 }));
+    assert_eq!(client.linked_id, None);
+    assert_eq!(client.protocol_errors, 4);
+}
+
+#[test]
+fn square_outside_of_map_is_ignored() {
+    let mut client = in_game();
+    let msgs = [
+        ServerMsg::BombExplode(BombExplode { w: 11, h: 0 }),
+        ServerMsg::PlayerPutBomb(PlayerPutBomb { id: 0, x: 0, y: usize::MAX }),
+        ServerMsg::DestroyItem(DestroyItem { w: u64::MAX, h: u64::MAX }),
+        ServerMsg::BombMove(BombMove { old_x: 1.0, old_y: 1.0, x: -3.0, y: 1.0 }),
+        ServerMsg::BombMove(BombMove { old_x: f32::NAN, old_y: 1.0, x: 1.0, y: 1.0 }),
+    ];
+    for msg in &msgs {
+        assert!(client.parse_rtp(msg.to_vec()).is_err(), "{:?}", msg);
+    }
+    assert_eq!(client.protocol_errors, msgs.len() as u64);
+}
+
+#[test]
+fn replaced_bombs_are_forgotten() {
+    let mut client = in_game();
+    client.parse_rtp(ServerMsg::PlayerPutBomb(PlayerPutBomb { id: 0, x: 1, y: 1 }).to_vec()).unwrap();
+    client.parse_rtp(ServerMsg::PlayerPutBomb(PlayerPutBomb { id: 0, x: 3, y: 1 }).to_vec()).unwrap();
+    client.parse_rtp(ServerMsg::DestroyItem(DestroyItem { w: 1, h: 1 }).to_vec()).unwrap();
+    assert_eq!(client.bombs.len(), 1);
+    let item: InteractiveItem = Box::new(Bonus::MoreBombs);
+    client.parse_rtp(ServerMsg::CreateItem(CreateItem { item: Some(item), w: 3, h: 1 }).to_vec()).unwrap();
+    assert!(client.bombs.is_empty());
+}
+
+#[test]
+fn inconsistent_map_is_rejected() {
+    let mut client = client();
+    let mut map = Map::new(11, 11);
+    map.squares.pop();
+    assert!(client.parse_rtp(ServerMsg::Map(MapMsg { map, names: Vec::new() }).to_vec()).is_err());
+    assert!(client.map.is_none());
+}
+
+#[test]
+fn map_with_invalid_dimensions_is_rejected() {
+    let dimensions = [(0, 11), (11, 0), (0, 0), (MAX_MAP_SIDE + 1, 1), (usize::MAX, 2)];
+    for (w, h) in dimensions.iter().cloned() {
+        let mut client = client();
+        let map = Map { w, h, squares: Vec::new(), players: Vec::new(), items: Vec::new() };
+        assert!(client.parse_rtp(ServerMsg::Map(MapMsg { map, names: Vec::new() }).to_vec()).is_err(), "{}x{}", w, h);
+        assert!(client.map.is_none());
+    }
+}
+
+#[test]
+fn process_rx_keeps_incomplete_packets() {
+    let mut client = client();
+    let data = framed(&[
+        ServerMsg::Joined(JoinedMsg { room: 3, success: true }),
+        ServerMsg::PlayerIdentity(PlayerIdentity { id: 2 }),
+    ]);
+    let (first, second) = data.split_at(data.len() - 3);
+    client.process_rx(first);
+    assert_eq!(client.current_room_id, Some(3));
+    assert_eq!(client.linked_id, None);
+    client.process_rx(second);
+    assert_eq!(client.linked_id, Some(2));
+    assert_eq!(client.protocol_errors, 0);
+}
+
+#[test]
+fn garbage_between_valid_packets() {
+    let mut client = in_game();
+    let mut data = framed(&[ServerMsg::Joined(JoinedMsg { room: 3, success: true })]);
+    data.extend_from_slice(&[0, 3, 0xc1, 0xff, 0x00]);
+    data.extend(framed(&[ServerMsg::PlayerDie(PlayerDie { id: 1 })]));
+    client.process_rx(&data);
+    assert_eq!(client.protocol_errors, 1);
+    assert!(client.map.as_ref().unwrap().players[1].dead);
+}
+
+#[test]
+fn huge_length_is_not_allocated() {
+    let mut client = in_game();
+    // ext 32 announcing almost 4 GB of data
+    let data = [0x81, 0xa8, b'm', b's', b'g', b'_', b't', b'y', b'p', b'e', 0xc9, 0xff, 0xff, 0xff, 0xf0, 0x01];
+    assert!(client.parse_rtp(data.to_vec()).is_err());
+}
+
+#[test]
+fn endless_effect_does_not_overflow() {
+    let mut client = in_game();
+    let effect = EffectUpdate { bonus: None, malus: None, remaining_ms: Some(u64::MAX) };
+    let updated = ServerMsg::PlayerUpdate(PlayerUpdate { id: 0, radius: 2, speed_factor: 1000, bomb: 1, effects: vec![effect] });
+    client.parse_rtp(updated.to_vec()).unwrap();
+    assert_eq!(client.effects[0].len(), 1);
+}
+
+proptest! {
+    #[test]
+    fn arbitrary_bytes_never_panic(chunks in prop::collection::vec(prop::collection::vec(any::<u8>(), 0..300), 0..20)) {
+        let mut client = in_game();
+        for chunk in &chunks {
+            client.process_rx(chunk);
+        }
+    }
+}
+
+#[test]
+fn unannounced_features_are_ignored() {
+    let mut client = client();
+    client.parse_rtp(ServerMsg::Welcome(WelcomeMsg { version: PROTOCOL_VERSION, capabilities: Vec::new() }).to_vec()).unwrap();
+    client.parse_rtp(ServerMsg::Map(MapMsg { map: open_map(11, 11), names: Vec::new() }).to_vec()).unwrap();
+    let game_over = ServerMsg::GameOver(GameOverMsg { winner: Some(0) });
+    assert_eq!(client.parse_rtp(game_over.to_vec()), Err(MsgError::NotNegotiated("game_over")));
+    assert!(client.round.as_ref().is_some_and(|round| round.result.is_none()));
+    let updated = ServerMsg::PlayerUpdate(PlayerUpdate { id: 0, radius: 5, speed_factor: 1000, bomb: 1, effects: Vec::new() });
+    assert_eq!(client.parse_rtp(updated.to_vec()), Err(MsgError::NotNegotiated("player_update")));
+    assert_eq!(client.map.as_ref().unwrap().players[0].radius, 2);
+    assert!(!client.predicts());
+}
@@ -0,0 +1,433 @@
+/**
+ * Copyright (c) 2019, Sébastien Blin <sebastien.blin@enconn.fr>
+ * All rights reserved.
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * * Redistributions of source code must retain the above copyright
+ *  notice, this list of conditions and the following disclaimer.
+ * * Redistributions in binary form must reproduce the above copyright
+ *  notice, this list of conditions and the following disclaimer in the
+ *  documentation and/or other materials provided with the distribution.
+ * * Neither the name of the University of California, Berkeley nor the
+ *  names of its contributors may be used to endorse or promote products
+ *  derived from this software without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE REGENTS AND CONTRIBUTORS ``AS IS'' AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+ * WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE REGENTS AND CONTRIBUTORS BE LIABLE FOR ANY
+ * DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+ * (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+ * LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ * ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+ * (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ **/
+
+// End to end tests of the client against the mock server on localhost
+
+mod common;
+
+use bomberust_client::{ Client, Handshake, OUTBOUND_QUEUE_SIZE };
+use bomberust_client::gen::item::Bonus;
+use bomberust_client::gen::map::Map;
+use bomberust_client::gen::utils::{ Direction, SquareType };
+use bomberust_client::net::*;
+use bomberust_client::net::diff_msg::*;
+use bomberust_client::net::msg::*;
+use common::mockserver::{ MockServer, Step, CA_CERT };
+use futures::sync::mpsc;
+use std::fs;
+use std::io::BufReader;
+use std::sync::{ Arc, Mutex };
+use std::thread;
+use std::time::{ Duration, Instant };
+use tokio_rustls::rustls::internal::pemfile;
+
+struct Session {
+    client: Arc<Mutex<Client>>,
+    state: Arc<Mutex<Option<ConnectionState>>>,
+    thread: thread::JoinHandle<()>,
+}
+
+impl Session {
+    fn send(&self, cmd: ClientCommand) {
+        self.client.lock().unwrap().send(cmd).unwrap();
+    }
+
+    fn state(&self) -> Option<ConnectionState> {
+        self.state.lock().unwrap().clone()
+    }
+
+    fn wait_closed(self) -> Option<ConnectionState> {
+        self.thread.join().unwrap();
+        self.state.lock().unwrap().clone()
+    }
+}
+
+fn config(server: &MockServer) -> TlsClientConfig {
+    let (tx, _) = mpsc::channel::<Vec<u8>>(OUTBOUND_QUEUE_SIZE);
+    let mut client = Client::new(tx);
+    client.player_name = Some(String::from("alice"));
+    TlsClientConfig {
+        server_state: Arc::new(Mutex::new(Some(ConnectionState::Connecting))),
+        addr: server.addr.to_string(),
+        server_name: String::from("localhost"),
+        cert: String::from(CA_CERT),
+        client_cert: String::new(),
+        client_key: String::new(),
+        trust_on_first_use: false,
+        fingerprint: String::new(),
+        reconnect: ReconnectPolicy { max_attempts: 0, ..Default::default() },
+        heartbeat: HeartbeatPolicy { interval: Duration::from_secs(60), ..Default::default() },
+        handshake_timeout: HANDSHAKE_TIMEOUT,
+        client: Arc::new(Mutex::new(client)),
+    }
+}
+
+fn connect(config: TlsClientConfig) -> Session {
+    let (tx, rx) = mpsc::channel::<Vec<u8>>(OUTBOUND_QUEUE_SIZE);
+    config.client.lock().unwrap().tx = tx;
+    Session {
+        client: config.client.clone(),
+        state: config.server_state.clone(),
+        thread: thread::spawn(move || TlsClient::start(&config, rx)),
+    }
+}
+
+fn wait_until<F: Fn(&Client) -> bool>(session: &Session, what: &str, condition: F) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !condition(&session.client.lock().unwrap()) {
+        if Instant::now() > deadline {
+            panic!("Timeout while waiting for {} (state: {:?})", what, session.state());
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+#[test]
+fn connect_lobby_room_game() {
+    let map = Map::new(11, 11);
+    let server = MockServer::start(vec![vec![
+        Step::Handshake(PROTOCOL_VERSION),
+        Step::Expect(ClientCommand::Player { name: String::from("alice") }),
+        Step::Send(ServerMsg::Joined(JoinedMsg { room: 0, success: true })),
+        Step::Expect(ClientCommand::Create),
+        Step::Send(ServerMsg::Joined(JoinedMsg { room: 42, success: true })),
+        Step::Expect(ClientCommand::Launch),
+        Step::Send(ServerMsg::Map(MapMsg { map: map.clone(), names: vec![String::from("alice"), String::from("bob")] })),
+        Step::Send(ServerMsg::PlayerIdentity(PlayerIdentity { id: 0 })),
+        Step::Send(ServerMsg::PlayerMove(PlayerMove { id: 0, x: 2.5, y: 1.5, seq: None })),
+        Step::Expect(ClientCommand::Move { direction: Direction::East, seq: None }),
+        Step::Expect(ClientCommand::Bomb),
+        Step::Send(ServerMsg::PlayerPutBomb(PlayerPutBomb { id: 0, x: 2, y: 1 })),
+        Step::Send(ServerMsg::BombExplode(BombExplode { w: 2, h: 1 })),
+        Step::Send(ServerMsg::PlayerUpdate(PlayerUpdate { id: 0, radius: 3, speed_factor: 1500, bomb: 2, effects: vec![
+            EffectUpdate { bonus: Some(Bonus::ImproveSpeed), malus: None, remaining_ms: Some(5000) },
+        ] })),
+        Step::Send(ServerMsg::PlayerDie(PlayerDie { id: 1 })),
+        Step::Send(ServerMsg::GameOver(GameOverMsg { winner: Some(0) })),
+    ]]);
+    let session = connect(config(&server));
+
+    wait_until(&session, "the lobby", |c| c.current_room_id == Some(0));
+    assert_eq!(session.state(), Some(ConnectionState::Connected));
+    session.send(ClientCommand::Create);
+    wait_until(&session, "the room", |c| c.current_room_id == Some(42));
+    session.send(ClientCommand::Launch);
+    wait_until(&session, "the game", |c| {
+        c.linked_id == Some(0) && c.map.as_ref().is_some_and(|m| m.players[0].x == 2.5)
+    });
+    session.send(ClientCommand::Move { direction: Direction::East, seq: None });
+    session.send(ClientCommand::Bomb);
+    wait_until(&session, "the end of the game", |c| c.round.as_ref().is_some_and(|r| r.result.is_some()));
+    {
+        let client = session.client.lock().unwrap();
+        assert_eq!(client.player_names[1], "bob");
+        assert_eq!(client.map.as_ref().unwrap().players[0].radius, 3);
+        assert_eq!(client.effects[0][0].bonus, Some(Bonus::ImproveSpeed));
+        let result = client.round.as_ref().unwrap().result.clone().unwrap();
+        assert_eq!(result.winner, Some(0));
+        assert_eq!(result.ranking.first(), Some(&0));
+        assert_eq!(result.ranking.last(), Some(&1));
+        assert!(!client.is_dead());
+        assert!(client.bombs.is_empty());
+        assert_eq!((client.flames[0].x, client.flames[0].y), (2, 1));
+    }
+    session.client.lock().unwrap().leave_game();
+    assert!(session.client.lock().unwrap().map.is_none());
+
+    assert_eq!(session.wait_closed(), Some(ConnectionState::Disconnected));
+    let received = server.join().unwrap();
+    assert_eq!(received.len(), 5);
+    assert_eq!(received[4], ClientCommand::Bomb);
+}
+
+#[test]
+fn unknown_ca_is_rejected() {
+    let server = MockServer::start(vec![vec![]]);
+    let mut config = config(&server);
+    config.cert = String::new(); // Public CAs only
+    match connect(config).wait_closed() {
+        Some(ConnectionState::Failed(reason)) => assert!(reason.contains("Certificate verification failed"), "{}", reason),
+        state => panic!("Unexpected state {:?}", state),
+    }
+    let _ = server.join();
+}
+
+#[test]
+fn server_name_defaults_to_the_host() {
+    let server = MockServer::start(vec![vec![
+        Step::Handshake(PROTOCOL_VERSION),
+        Step::Expect(ClientCommand::Player { name: String::from("alice") }),
+    ]]);
+    let mut config = config(&server);
+    config.addr = format!("localhost:{}", server.addr.port());
+    config.server_name = String::new();
+
+    assert_eq!(connect(config).wait_closed(), Some(ConnectionState::Disconnected));
+    server.join().unwrap();
+}
+
+#[test]
+fn ip_host_needs_a_server_name() {
+    let server = MockServer::start(vec![]);
+    let mut config = config(&server);
+    config.server_name = String::new();
+    match connect(config).wait_closed() {
+        Some(ConnectionState::Failed(reason)) => assert!(reason.contains("127.0.0.1 is an IP address"), "{}", reason),
+        state => panic!("Unexpected state {:?}", state),
+    }
+    server.join().unwrap();
+}
+
+#[test]
+fn first_certificate_must_be_trusted() {
+    let server = MockServer::start(vec![vec![]]);
+    let mut config = config(&server);
+    config.trust_on_first_use = true;
+    let mut pem = BufReader::new(fs::File::open(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/server.pem")).unwrap());
+    let expected = fingerprint(&pemfile::certs(&mut pem).unwrap()[0].0);
+    assert_eq!(connect(config).wait_closed(), Some(ConnectionState::UntrustedCertificate(expected)));
+    let _ = server.join();
+}
+
+#[test]
+fn reconnect_and_rejoin_room() {
+    let server = MockServer::start(vec![
+        vec![
+            Step::Handshake(PROTOCOL_VERSION),
+            Step::Expect(ClientCommand::Player { name: String::from("alice") }),
+            Step::Expect(ClientCommand::Join { room: 7 }),
+            Step::Send(ServerMsg::Joined(JoinedMsg { room: 7, success: true })),
+            Step::Sleep(Duration::from_millis(100)),
+        ],
+        vec![
+            Step::Handshake(PROTOCOL_VERSION),
+            Step::Expect(ClientCommand::Player { name: String::from("alice") }),
+            Step::Expect(ClientCommand::Join { room: 7 }),
+        ],
+    ]);
+    let mut config = config(&server);
+    config.reconnect = ReconnectPolicy {
+        max_attempts: 1,
+        initial_delay: Duration::from_millis(10),
+        max_delay: Duration::from_millis(10),
+    };
+    let session = connect(config);
+    // Commands queued before the connection would go before the player details
+    wait_until(&session, "the connection", |_| session.state() == Some(ConnectionState::Connected));
+    session.send(ClientCommand::Join { room: 7 });
+    wait_until(&session, "the room", |c| c.current_room_id == Some(7));
+
+    assert_eq!(session.wait_closed(), Some(ConnectionState::Disconnected));
+    assert_eq!(server.join().unwrap().len(), 4);
+}
+
+#[test]
+fn reconnect_while_server_is_unreachable() {
+    let server = MockServer::start(vec![
+        vec![
+            Step::Handshake(PROTOCOL_VERSION),
+            Step::Expect(ClientCommand::Player { name: String::from("alice") }),
+            Step::Expect(ClientCommand::Join { room: 7 }),
+            Step::Send(ServerMsg::Joined(JoinedMsg { room: 7, success: true })),
+            Step::Offline(Duration::from_millis(200)),
+        ],
+        vec![
+            Step::Handshake(PROTOCOL_VERSION),
+            Step::Expect(ClientCommand::Player { name: String::from("alice") }),
+            Step::Expect(ClientCommand::Join { room: 7 }),
+        ],
+    ]);
+    let mut config = config(&server);
+    // The first attempts find nobody listening
+    config.reconnect = ReconnectPolicy {
+        max_attempts: 20,
+        initial_delay: Duration::from_millis(50),
+        max_delay: Duration::from_millis(50),
+    };
+    let session = connect(config);
+    wait_until(&session, "the connection", |_| session.state() == Some(ConnectionState::Connected));
+    session.send(ClientCommand::Join { room: 7 });
+    wait_until(&session, "the reconnection", |_| matches!(session.state(), Some(ConnectionState::Reconnecting(_))));
+
+    assert_eq!(server.join().unwrap().len(), 4);
+    assert_eq!(session.wait_closed(), Some(ConnectionState::Disconnected));
+}
+
+#[test]
+fn heartbeat_measures_rtt() {
+    let server = MockServer::start(vec![vec![
+        Step::Handshake(PROTOCOL_VERSION),
+        Step::Expect(ClientCommand::Player { name: String::from("alice") }),
+        Step::Expect(ClientCommand::Leave),
+    ]]);
+    let mut config = config(&server);
+    config.heartbeat.interval = Duration::from_millis(20);
+    let session = connect(config);
+    wait_until(&session, "a pong", |c| c.heartbeat.rtt().is_some());
+    session.send(ClientCommand::Leave);
+
+    assert_eq!(session.wait_closed(), Some(ConnectionState::Disconnected));
+    server.join().unwrap();
+}
+
+#[test]
+fn missing_pongs_close_the_connection() {
+    let server = MockServer::start(vec![vec![
+        Step::Handshake(PROTOCOL_VERSION),
+        Step::Expect(ClientCommand::Player { name: String::from("alice") }),
+        Step::Ignore(Duration::from_secs(3)),
+    ]]);
+    let mut config = config(&server);
+    config.heartbeat = HeartbeatPolicy { interval: Duration::from_millis(20), max_missed: 3 };
+    let start = Instant::now();
+
+    assert_eq!(connect(config).wait_closed(), Some(ConnectionState::Disconnected));
+    assert!(start.elapsed() < Duration::from_secs(3));
+    let _ = server.join();
+}
+
+#[test]
+fn server_without_ping_is_not_timed_out() {
+    let server = MockServer::start(vec![vec![
+        Step::HandshakeWith(PROTOCOL_VERSION, vec!["input_sequence"]),
+        Step::Expect(ClientCommand::Player { name: String::from("alice") }),
+        // Pings would not be answered while sleeping
+        Step::Sleep(Duration::from_millis(300)),
+        Step::Send(ServerMsg::Joined(JoinedMsg { room: 1, success: true })),
+        Step::Expect(ClientCommand::Leave),
+    ]]);
+    let mut config = config(&server);
+    config.heartbeat = HeartbeatPolicy { interval: Duration::from_millis(20), max_missed: 3 };
+    let session = connect(config);
+    wait_until(&session, "the room", |c| c.current_room_id == Some(1));
+    assert_eq!(session.state(), Some(ConnectionState::Connected));
+    {
+        let client = session.client.lock().unwrap();
+        assert!(client.supports("input_sequence"));
+        assert!(!client.supports("ping"));
+        assert_eq!(client.heartbeat.missed(), 0);
+    }
+    session.send(ClientCommand::Leave);
+
+    assert_eq!(session.wait_closed(), Some(ConnectionState::Disconnected));
+    assert_eq!(server.join().unwrap().len(), 2);
+}
+
+#[test]
+fn incompatible_server_is_reported() {
+    let server = MockServer::start(vec![vec![
+        Step::Handshake(PROTOCOL_VERSION + 1),
+        Step::Sleep(Duration::from_millis(500)),
+    ]]);
+    let mut config = config(&server);
+    config.reconnect.max_attempts = 3;
+    match connect(config).wait_closed() {
+        Some(ConnectionState::Incompatible(reason)) => assert!(reason.contains(&format!("version {}", PROTOCOL_VERSION + 1)), "{}", reason),
+        state => panic!("Unexpected state {:?}", state),
+    }
+    server.join().unwrap();
+}
+
+#[test]
+fn server_without_handshake_is_incompatible() {
+    let server = MockServer::start(vec![vec![
+        Step::Ignore(Duration::from_millis(100)),
+    ]]);
+    match connect(config(&server)).wait_closed() {
+        Some(ConnectionState::Incompatible(_)) => {},
+        state => panic!("Unexpected state {:?}", state),
+    }
+    server.join().unwrap();
+}
+
+#[test]
+fn server_without_welcome_is_used_with_version_1() {
+    let server = MockServer::start(vec![vec![
+        // An old server: the hello is read but never answered
+        Step::Expect(ClientCommand::Hello {
+            version: PROTOCOL_VERSION,
+            min_version: MIN_PROTOCOL_VERSION,
+            capabilities: CAPABILITIES.iter().map(|c| c.to_string()).collect(),
+        }),
+        Step::Expect(ClientCommand::Player { name: String::from("alice") }),
+        Step::Sleep(Duration::from_millis(300)),
+        Step::Send(ServerMsg::Joined(JoinedMsg { room: 0, success: true })),
+        Step::Expect(ClientCommand::Create),
+    ]]);
+    let mut config = config(&server);
+    config.handshake_timeout = Duration::from_millis(100);
+    config.heartbeat = HeartbeatPolicy { interval: Duration::from_millis(20), max_missed: 3 };
+    let session = connect(config);
+    wait_until(&session, "the fallback", |c| c.handshake != Handshake::Pending);
+    assert_eq!(session.state(), Some(ConnectionState::Connected));
+    assert_eq!(session.client.lock().unwrap().handshake, Handshake::Done { version: 1, capabilities: Vec::new() });
+    wait_until(&session, "the lobby", |c| c.current_room_id == Some(0));
+    session.send(ClientCommand::Create);
+
+    assert_eq!(session.wait_closed(), Some(ConnectionState::Disconnected));
+    assert_eq!(server.join().unwrap().len(), 3);
+}
+
+#[test]
+fn moves_are_predicted_and_reconciled() {
+    let mut map = Map::new(11, 11);
+    for square in &mut map.squares {
+        square.sq_type = SquareType::Empty;
+    }
+    for item in &mut map.items {
+        *item = None;
+    }
+    map.players[0].x = 1.5;
+    map.players[0].y = 1.5;
+    let server = MockServer::start(vec![vec![
+        Step::Handshake(PROTOCOL_VERSION),
+        Step::Expect(ClientCommand::Player { name: String::from("alice") }),
+        Step::Send(ServerMsg::Map(MapMsg { map, names: Vec::new() })),
+        Step::Send(ServerMsg::PlayerIdentity(PlayerIdentity { id: 0 })),
+        Step::Expect(ClientCommand::Move { direction: Direction::East, seq: Some(1) }),
+        Step::Expect(ClientCommand::Move { direction: Direction::East, seq: Some(2) }),
+        // Only the first move is processed, and corrected
+        Step::Send(ServerMsg::PlayerMove(PlayerMove { id: 0, x: 1.5, y: 1.5, seq: Some(1) })),
+        Step::Expect(ClientCommand::Leave),
+    ]]);
+    let session = connect(config(&server));
+    wait_until(&session, "the game", |c| c.linked_id == Some(0) && c.map.is_some() && c.predicts());
+
+    {
+        let mut client = session.client.lock().unwrap();
+        client.move_to(Direction::East).unwrap();
+        client.move_to(Direction::East).unwrap();
+        // Moved before any answer of the server
+        assert_eq!(client.player_position(0, Instant::now()), Some((1.7, 1.5)));
+    }
+    wait_until(&session, "the reconciliation", |c| c.prediction.pending() == 1);
+    assert_eq!(session.client.lock().unwrap().player_position(0, Instant::now()), Some((1.6, 1.5)));
+    session.send(ClientCommand::Leave);
+
+    assert_eq!(session.wait_closed(), Some(ConnectionState::Disconnected));
+    server.join().unwrap();
+}